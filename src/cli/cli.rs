@@ -0,0 +1,86 @@
+use crate::ripgrep::ripgrep::CaseMode;
+use std::io::{IsTerminal, Read};
+use std::sync::Arc;
+
+pub const USAGE: &str = "\
+Usage: rs-fzf [OPTIONS] [PATH...]
+
+Options:
+  -q, --query <QUERY>    Start with this query filled in
+  -p, --path <PATH>      Search this path (repeatable; bare arguments are paths too)
+  -s, --case-sensitive   Match case
+  -i, --ignore-case      Ignore case
+  -S, --smart-case       Ignore case unless the query has uppercase letters
+  -F, --fixed-strings    Treat the query as a literal string
+  -w, --word-regexp      Only match whole words
+      --hidden           Search hidden files
+      --run              Start the search immediately
+  -h, --help             Print this help
+
+A PATH of `-` searches input piped to rs-fzf (`cat big.log | rs-fzf -`) instead of the paths.";
+
+/// Initial state requested on the command line; anything left unset keeps the saved value.
+#[derive(Debug, Default)]
+pub struct LaunchArgs {
+    pub query: Option<String>,
+    pub paths: Vec<String>,
+    pub case_mode: Option<CaseMode>,
+    pub fixed_string: bool,
+    pub whole_word: bool,
+    pub search_hidden: bool,
+    pub run: bool,
+    pub help: bool,
+    /// Set by a `-` path: read stdin at startup. Without it stdin is never touched, so a
+    /// launcher or shell that leaves stdin open can't stall startup.
+    pub read_stdin: bool,
+    /// Piped input read at startup; searched in place of the paths.
+    pub stdin: Option<Arc<Vec<u8>>>,
+}
+
+impl LaunchArgs {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut launch = LaunchArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Accept `--query=foo` as well as `--query foo`.
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value.clone().or_else(|| args.next()).ok_or_else(|| format!("{} needs a value", name))
+            };
+            match flag.as_str() {
+                "-q" | "--query" => launch.query = Some(value("--query")?),
+                "-p" | "--path" => launch.paths.push(value("--path")?),
+                "-s" | "--case-sensitive" => launch.case_mode = Some(CaseMode::Sensitive),
+                "-i" | "--ignore-case" => launch.case_mode = Some(CaseMode::Insensitive),
+                "-S" | "--smart-case" => launch.case_mode = Some(CaseMode::Smart),
+                "-F" | "--fixed-strings" => launch.fixed_string = true,
+                "-w" | "--word-regexp" => launch.whole_word = true,
+                "--hidden" => launch.search_hidden = true,
+                "--run" => launch.run = true,
+                "-h" | "--help" => launch.help = true,
+                "-" => launch.read_stdin = true,
+                _ if flag.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ => launch.paths.push(arg),
+            }
+        }
+        Ok(launch)
+    }
+}
+
+/// Reads all of stdin when it is piped rather than a terminal. Empty input (as from `/dev/null`
+/// when launched from a desktop) counts as nothing piped.
+pub fn read_piped_stdin() -> Option<Arc<Vec<u8>>> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+    let mut input = Vec::new();
+    match stdin.read_to_end(&mut input) {
+        Ok(_) if !input.is_empty() => Some(Arc::new(input)),
+        _ => None,
+    }
+}
@@ -0,0 +1,164 @@
+use crate::ripgrep::ripgrep::split_args;
+use std::path::Path;
+use std::process::Command;
+
+/// Opens `path` at `line` (and `column`, when known) using, in order of preference: the user's
+/// command template (tokenized like `split_args`, then `{path}`, `{line}`, `{col}` and the
+/// 0-based byte `{offset}` are substituted), `$EDITOR`, or the platform's default opener.
+pub fn open_in_editor(template: &str, path: &str, line: u64, column: Option<u64>, offset: Option<u64>) -> Result<(), String> {
+    let column = column.unwrap_or(1);
+    let args = if !template.trim().is_empty() {
+        split_args(template)
+            .map_err(|e| format!("Editor command: {}", e))?
+            .into_iter()
+            .map(|part| {
+                part.replace("{path}", path)
+                    .replace("{line}", &line.to_string())
+                    .replace("{col}", &column.to_string())
+                    .replace("{offset}", &offset.unwrap_or(0).to_string())
+            })
+            .collect()
+    } else if let Ok(editor) = std::env::var("EDITOR")
+        && !editor.trim().is_empty()
+    {
+        editor_args(&editor, path, line, column)
+    } else {
+        platform_open_args(path)
+    };
+
+    spawn(&args)
+}
+
+/// Opens several files in one editor invocation, each at its `(path, line, offset)`. Template
+/// tokens containing a placeholder are repeated once per file; editors from `$EDITOR` that accept
+/// `path:line` arguments get them, others just get the paths. Without either, each file is
+/// handed to the platform opener separately.
+pub fn open_all_in_editor(template: &str, files: &[(String, u64, Option<u64>)]) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let args = if !template.trim().is_empty() {
+        let tokens = split_args(template).map_err(|e| format!("Editor command: {}", e))?;
+        let is_placeholder = |t: &String| ["{path}", "{line}", "{col}", "{offset}"].iter().any(|p| t.contains(p));
+        let first = tokens.iter().position(is_placeholder).unwrap_or(tokens.len());
+        let last = tokens.iter().rposition(is_placeholder).map_or(first, |i| i + 1);
+        let mut args: Vec<String> = tokens[..first].to_vec();
+        for (path, line, offset) in files {
+            args.extend(tokens[first..last].iter().map(|t| {
+                t.replace("{path}", path)
+                    .replace("{line}", &line.to_string())
+                    .replace("{col}", "1")
+                    .replace("{offset}", &offset.unwrap_or(0).to_string())
+            }));
+        }
+        args.extend(tokens[last..].iter().cloned());
+        args
+    } else if let Ok(editor) = std::env::var("EDITOR")
+        && !editor.trim().is_empty()
+    {
+        let mut args: Vec<String> = editor.split_whitespace().map(String::from).collect();
+        let program = Path::new(&args[0]).file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        match program.as_str() {
+            "code" | "code-insiders" | "codium" => {
+                args.push("-g".to_string());
+                args.extend(files.iter().map(|(path, line, _)| format!("{}:{}", path, line)));
+            }
+            "subl" | "hx" | "zed" => {
+                args.extend(files.iter().map(|(path, line, _)| format!("{}:{}", path, line)));
+            }
+            _ => args.extend(files.iter().map(|(path, _, _)| path.clone())),
+        }
+        args
+    } else {
+        for (path, _, _) in files {
+            spawn(&platform_open_args(path))?;
+        }
+        return Ok(());
+    };
+
+    spawn(&args)
+}
+
+fn editor_args(editor: &str, path: &str, line: u64, column: u64) -> Vec<String> {
+    let mut args: Vec<String> = editor.split_whitespace().map(String::from).collect();
+    let program = std::path::Path::new(&args[0])
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    match program.as_str() {
+        "code" | "code-insiders" | "codium" => {
+            args.push("-g".to_string());
+            args.push(format!("{}:{}:{}", path, line, column));
+        }
+        "nano" => {
+            args.push(format!("+{},{}", line, column));
+            args.push(path.to_string());
+        }
+        "vim" | "nvim" | "vi" | "gvim" | "emacs" | "kak" | "micro" => {
+            args.push(format!("+{}", line));
+            args.push(path.to_string());
+        }
+        "subl" | "hx" | "zed" => {
+            args.push(format!("{}:{}:{}", path, line, column));
+        }
+        _ => args.push(path.to_string()),
+    }
+    args
+}
+
+fn platform_open_args(path: &str) -> Vec<String> {
+    if cfg!(target_os = "windows") {
+        vec!["cmd".to_string(), "/C".to_string(), "start".to_string(), String::new(), path.to_string()]
+    } else if cfg!(target_os = "macos") {
+        vec!["open".to_string(), path.to_string()]
+    } else {
+        vec!["xdg-open".to_string(), path.to_string()]
+    }
+}
+
+/// Shows `path` in the platform file manager: selected where that is supported (Windows,
+/// macOS), otherwise by opening its parent directory.
+pub fn reveal_in_file_manager(path: &str) -> Result<(), String> {
+    let args = if cfg!(target_os = "windows") {
+        vec!["explorer".to_string(), format!("/select,{}", path)]
+    } else if cfg!(target_os = "macos") {
+        vec!["open".to_string(), "-R".to_string(), path.to_string()]
+    } else {
+        let dir = Path::new(path)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        vec!["xdg-open".to_string(), dir.display().to_string()]
+    };
+    spawn(&args)
+}
+
+/// Runs a user command template for a result. The template is tokenized shell-style first, so
+/// substituted `{path}`, `{line}` and `{dir}` values stay single arguments even with spaces.
+pub fn run_command_template(template: &str, path: &str, line: u64) -> Result<(), String> {
+    let dir = Path::new(path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .display()
+        .to_string();
+    let args: Vec<String> = split_args(template)
+        .map_err(|e| format!("Custom command: {}", e))?
+        .into_iter()
+        .map(|arg| arg.replace("{path}", path).replace("{line}", &line.to_string()).replace("{dir}", &dir))
+        .collect();
+    spawn(&args)
+}
+
+fn spawn(args: &[String]) -> Result<(), String> {
+    let (program, rest) = args.split_first().ok_or_else(|| "Editor command is empty.".to_string())?;
+    Command::new(program)
+        .args(rest)
+        .spawn()
+        .map(|mut child| {
+            // Reap the editor in the background so it doesn't linger as a zombie.
+            std::thread::spawn(move || child.wait().ok());
+        })
+        .map_err(|e| format!("Failed to launch '{}': {}", program, e))
+}
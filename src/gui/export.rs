@@ -0,0 +1,57 @@
+use crate::ripgrep::ripgrep::GuiMatch;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct ExportedMatch<'a> {
+    path: &'a str,
+    line: u64,
+    text: &'a str,
+}
+
+/// Writes `results` to `path`, choosing JSON, CSV or plain text from the file extension.
+pub fn export_results(path: &Path, results: &[GuiMatch]) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let contents = match extension.as_str() {
+        "json" => to_json(results)?,
+        "csv" => to_csv(results),
+        _ => to_plain_text(results),
+    };
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn to_json(results: &[GuiMatch]) -> Result<String, String> {
+    let exported: Vec<ExportedMatch> = results
+        .iter()
+        .map(|m| ExportedMatch { path: &m.path, line: m.line_number, text: &m.line_text })
+        .collect();
+    serde_json::to_string_pretty(&exported).map_err(|e| format!("Failed to serialize results: {}", e))
+}
+
+fn to_csv(results: &[GuiMatch]) -> String {
+    let mut out = String::from("path,line,text\n");
+    for m in results {
+        out.push_str(&format!("{},{},{}\n", csv_field(&m.path), m.line_number, csv_field(&m.line_text)));
+    }
+    out
+}
+
+/// One `path:line:text` entry per line.
+pub fn to_plain_text<'a>(results: impl IntoIterator<Item = &'a GuiMatch>) -> String {
+    results
+        .into_iter()
+        .map(|m| format!("{}:{}:{}\n", m.path, m.line_number, m.line_text))
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
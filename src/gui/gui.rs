@@ -1,163 +1,2853 @@
-use crate::ripgrep::ripgrep::{run_ripgrep, GuiMatch, SearchResult}; 
-use crossbeam_channel::{unbounded, Receiver, TryRecvError}; 
+use crate::cli::cli::LaunchArgs;
+use crate::gui::editor::{open_all_in_editor, open_in_editor, reveal_in_file_manager, run_command_template};
+use crate::gui::export::{export_results, to_plain_text};
+use crate::gui::preview::PreviewCache;
+use crate::ripgrep::ripgrep::{build_rg_args, command_line, is_valid_filesize, detect_rg, format_version, resolve_executable, join_args, run_ripgrep, split_args, MIN_RG_GLOB_CASE_INSENSITIVE, MIN_RG_VERSION, STDIN_PATH, BinaryMode, CaseMode, GuiMatch, IgnoreLevel, RgInfo, RgOptions, RgSort, SearchResult, SearchStats}; 
+use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError}; 
 use directories::UserDirs;
+use egui::text::{LayoutJob, TextFormat};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread; 
+use std::time::{Duration, Instant};
 
-pub struct MyApp {
+/// Ripgrep type names offered in the type filter menus (see `rg --type-list`).
+const COMMON_TYPES: &[&str] = &[
+    "c", "cpp", "csharp", "css", "go", "html", "java", "js", "json", "kotlin", "lua", "make",
+    "md", "php", "py", "ruby", "rust", "sh", "sql", "swift", "toml", "ts", "xml", "yaml",
+];
+
+/// Encodings offered for `--encoding`; rg transcodes them to UTF-8 before emitting JSON.
+const COMMON_ENCODINGS: &[&str] = &[
+    "utf-8", "utf-16le", "utf-16be", "latin1", "windows-1251", "koi8-r", "shift_jis", "euc-jp",
+    "euc-kr", "gbk", "big5",
+];
+
+/// File extensions egui_extras' built-in highlighter knows; other files stay plain.
+const SYNTAX_EXTENSIONS: &[&str] = &["c", "h", "hpp", "cpp", "py", "rs", "toml"];
+
+/// How the results list is ordered for display; the underlying `results` keep arrival order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+enum ResultSort {
+    #[default]
+    FileOrder,
+    Path,
+    LineNumber,
+    /// Newest files first; rg does the ordering, so results display in arrival order.
+    Modified,
+}
+
+impl ResultSort {
+    fn label(self) -> &'static str {
+        match self {
+            ResultSort::FileOrder => "File order",
+            ResultSort::Path => "Path A–Z",
+            ResultSort::LineNumber => "Line number",
+            ResultSort::Modified => "Newest first",
+        }
+    }
+
+    /// The sort rg itself should apply for this display order.
+    fn rg_sort(self) -> RgSort {
+        match self {
+            ResultSort::Path => RgSort::Path,
+            ResultSort::Modified => RgSort::ModifiedNewest,
+            ResultSort::FileOrder | ResultSort::LineNumber => RgSort::None,
+        }
+    }
+}
+
+/// How much room each result row takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+enum RowDensity {
+    /// Boxed rows with a header line, context lines and the match line.
+    #[default]
+    Comfortable,
+    /// One `path:line: text` line per result, without box or context.
+    Compact,
+}
+
+/// Font family for result lines and their context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+enum ResultFont {
+    #[default]
+    Monospace,
+    Proportional,
+}
+
+impl ResultFont {
+    fn family(self) -> egui::FontFamily {
+        match self {
+            ResultFont::Monospace => egui::FontFamily::Monospace,
+            ResultFont::Proportional => egui::FontFamily::Proportional,
+        }
+    }
+}
+
+/// egui's own size for `TextStyle::Monospace`.
+const DEFAULT_RESULT_FONT_SIZE: f32 = 12.0;
+
+/// Opening more files than this at once asks for confirmation first.
+const OPEN_ALL_CONFIRM_THRESHOLD: usize = 20;
+
+/// An action waiting on the confirmation dialog.
+#[derive(Clone, Copy)]
+enum PendingAction {
+    Search,
+    OpenAll,
+    OpenSelected,
+}
+
+/// Everything `run_ripgrep` needs, kept so the last search can be repeated.
+#[derive(Clone)]
+struct SearchRequest {
+    query: String,
+    paths: Vec<String>,
+    options: RgOptions,
+    input: Option<Arc<Vec<u8>>>,
+}
+
+/// A search handed to a `SearchWorker`, with where its results go and how to stop it.
+struct SearchJob {
+    request: SearchRequest,
+    sender: Sender<SearchResult>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// A long-lived thread that runs one tab's searches one after another, so rapid
+/// searching doesn't spawn a thread per search. It exits once the tab drops it.
+struct SearchWorker {
+    jobs: Sender<SearchJob>,
+}
+
+impl SearchWorker {
+    fn spawn() -> Self {
+        let (jobs, queue) = unbounded::<SearchJob>();
+        thread::spawn(move || {
+            while let Ok(mut job) = queue.recv() {
+                // Only the newest queued search matters; older ones were superseded.
+                while let Ok(newer) = queue.try_recv() {
+                    job = newer;
+                }
+                if job.cancel.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let SearchRequest { query, paths, options, input } = job.request;
+                run_ripgrep(query, paths, options, input, job.sender, job.cancel);
+            }
+        });
+        Self { jobs }
+    }
+
+    /// Queues `request`. The caller cancels the previous search first, so the worker is free
+    /// as soon as that rg process has been killed.
+    fn submit(&self, request: SearchRequest, sender: Sender<SearchResult>, cancel: Arc<AtomicBool>) {
+        // The thread only exits when `jobs` is dropped, so sending can't fail while `self` lives.
+        let _ = self.jobs.send(SearchJob { request, sender, cancel });
+    }
+}
+
+/// What the user asked for on a result row this frame.
+enum RowAction {
+    /// A click, read together with the Shift and Ctrl/Cmd modifiers held at the time.
+    Select(usize),
+    Open(usize),
+    Reveal(usize),
+    RunCommand(usize),
+}
+
+/// One entry of the results list as drawn: a file header when grouping by file, or a result.
+#[derive(Clone, Copy, PartialEq)]
+enum ListItem {
+    /// Header of the file whose rows are `display_order[start..end]`.
+    Header { start: usize, end: usize },
+    Row(usize),
+}
+
+/// A named snapshot of the query, paths and every rg option, recalled from the presets menu.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct SavedSearch {
+    name: String,
+    query: String,
+    paths: Vec<String>,
+    options: RgOptions,
+    /// The "Literal block" mode, which `options` only records as `-F -U`.
+    #[serde(default)]
+    literal_block: bool,
+    /// The "Whole-file pattern" preset, which `options` only records as `-U --multiline-dotall`.
+    #[serde(default)]
+    whole_file_pattern: bool,
+}
+
+/// Preferences shared by every tab: external tools, history, saved searches and the theme.
+/// Persisted as part of `MyApp`.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Settings {
+    editor_command: String,
+    /// Per-result custom command, e.g. a terminal opened in the result's directory.
+    custom_command: String,
+    rg_path: String,
+    /// What `rg --version` reported at startup or on Test; `None` after the path is edited.
+    #[serde(skip)]
+    rg_info: Option<Result<RgInfo, String>>,
+    query_history: Vec<String>,
+    /// Searched paths, most recent first.
+    recent_paths: Vec<String>,
+    saved_searches: Vec<SavedSearch>,
+    /// `None` follows the OS theme.
+    dark_mode: Option<bool>,
+    #[serde(skip)]
+    show_about: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            editor_command: String::new(),
+            custom_command: String::new(),
+            rg_path: "rg".to_string(),
+            rg_info: None,
+            query_history: Vec::new(),
+            recent_paths: Vec::new(),
+            saved_searches: Vec::new(),
+            dark_mode: None,
+            show_about: false,
+        }
+    }
+}
+
+impl Settings {
+    fn remember_query(&mut self, query: &str) {
+        if query.trim().is_empty() {
+            return;
+        }
+        self.query_history.retain(|q| q != query);
+        self.query_history.insert(0, query.to_string());
+        self.query_history.truncate(MAX_QUERY_HISTORY);
+    }
+
+    fn remember_paths(&mut self, paths: &[String]) {
+        for path in paths.iter().rev() {
+            self.recent_paths.retain(|p| p != path);
+            self.recent_paths.insert(0, path.clone());
+        }
+        self.recent_paths.truncate(MAX_RECENT_PATHS);
+    }
+
+    /// Whether the detected rg is at least `min`; assumed so when it hasn't been detected.
+    fn rg_supports(&self, min: (u32, u32, u32)) -> bool {
+        match &self.rg_info {
+            Some(Ok(info)) => info.at_least(min),
+            _ => true,
+        }
+    }
+
+    /// Explains why searching can't work with the detected rg, if it is too old.
+    fn rg_too_old(&self) -> Option<String> {
+        match &self.rg_info {
+            Some(Ok(info)) if !info.at_least(MIN_RG_VERSION) => Some(format!(
+                "{} is too old: rs-fzf needs ripgrep {} or newer for --json output. Install a newer rg or point the rg path at one.",
+                info.version_line,
+                format_version(MIN_RG_VERSION),
+            )),
+            _ => None,
+        }
+    }
+
+    fn show_about_window(&mut self, ctx: &egui::Context) {
+        if !self.show_about {
+            return;
+        }
+        let rg_version = match &self.rg_info {
+            Some(Ok(info)) => info.version_line.clone(),
+            Some(Err(e)) => e.clone(),
+            None => "Not checked since the rg path changed; press Test in Options".to_string(),
+        };
+        let rg_location = resolve_executable(&self.rg_path)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| format!("{} (not found)", self.rg_path));
+        let report = format!(
+            "rs-fzf {}\nrg: {}\nrg path: {}\nPlatform: {} {}",
+            env!("CARGO_PKG_VERSION"),
+            rg_version,
+            rg_location,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+        );
+        egui::Window::new("About")
+            .open(&mut self.show_about)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Grid::new("about").num_columns(2).show(ui, |ui| {
+                    ui.label("Version:");
+                    ui.label(env!("CARGO_PKG_VERSION"));
+                    ui.end_row();
+                    ui.label("ripgrep:");
+                    ui.label(rg_version);
+                    ui.end_row();
+                    ui.label("rg path:");
+                    ui.label(rg_location);
+                    ui.end_row();
+                    ui.label("Platform:");
+                    ui.label(format!("{} {}", std::env::consts::OS, std::env::consts::ARCH));
+                    ui.end_row();
+                });
+                if ui.button("Copy").on_hover_text("Copy these details, e.g. for a bug report").clicked() {
+                    ui.ctx().copy_text(report);
+                }
+            });
+    }
+}
+
+/// One independent search: its form, options and results. Persisted across restarts via
+/// eframe storage as part of `MyApp`; transient search state is skipped.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct SearchTab {
+    #[serde(skip)]
     query: String,
-    path: String,
+    #[serde(skip)]
+    query_error: Option<String>,
+    #[serde(skip)]
+    replace: String,
+    paths: Vec<String>,
+    #[serde(skip)]
     results: Vec<GuiMatch>, 
+    #[serde(skip)]
+    max_row_lines: usize,
+    result_sort: ResultSort,
+    /// Shows results under one collapsible header per file.
+    group_by_file: bool,
+    /// Shows result paths relative to the search root they were found under.
+    relative_paths: bool,
+    /// Shows how long rg spent on each file in its group header.
+    show_timings: bool,
+    #[serde(skip)]
+    file_timings: HashMap<String, Duration>,
+    row_density: RowDensity,
+    result_font: ResultFont,
+    /// In points, like egui's own text sizes.
+    result_font_size: f32,
+    wrap_lines: bool,
+    /// Height of each result row with "Wrap lines" on, by result index. Rows are measured once
+    /// and kept while `wrapped_heights_key` (the wrap width and result font) stays the same.
+    #[serde(skip)]
+    wrapped_heights: Vec<f32>,
+    #[serde(skip)]
+    wrapped_heights_key: (f32, egui::FontId),
+    /// Keeps the results list pinned to the bottom while matches stream in.
+    auto_scroll: bool,
+    /// Colors result lines by language, guessed from the file extension.
+    syntax_highlighting: bool,
+    /// Appends each match's byte offset to its location, e.g. for logs and binary files.
+    show_offsets: bool,
+    /// Files whose group is collapsed. Keyed by path, so the state survives new matches
+    /// streaming in.
+    #[serde(skip)]
+    collapsed_files: HashSet<String>,
+    #[serde(skip)]
+    display_order: Vec<usize>,
+    #[serde(skip)]
+    display_order_key: (usize, ResultSort, String, bool),
+    /// Results whose row follows a row from the same file with lines skipped in between;
+    /// a divider is drawn above them.
+    #[serde(skip)]
+    block_starts: HashSet<usize>,
+    /// Distinct matched texts with their counts, most frequent first, for `-o` searches.
+    #[serde(skip)]
+    match_frequencies: Vec<(String, usize)>,
+    /// `results.len()` when `match_frequencies` was last rebuilt.
+    #[serde(skip)]
+    match_frequencies_key: usize,
+    /// Matches per file extension, e.g. `.rs`, counted over `results[..extension_counts_key]`.
+    #[serde(skip)]
+    extension_counts: HashMap<String, u64>,
+    #[serde(skip)]
+    extension_counts_key: usize,
+    /// Client-side, case-insensitive substring filter over result paths and lines.
+    #[serde(skip)]
+    result_filter: String,
+    /// The focused result: moved by the arrow keys, shown in the preview and the anchor of
+    /// Shift-click ranges.
+    #[serde(skip)]
+    selected_index: Option<usize>,
+    /// Every selected result, including `selected_index`; the target of the selection actions.
+    #[serde(skip)]
+    selection: HashSet<usize>,
+    #[serde(skip)]
+    scroll_to_selected: bool,
+    /// Scroll offset and height of the results list as of the last frame.
+    #[serde(skip)]
+    results_viewport: (f32, f32),
+    /// Height of everything in the results list as of the last frame.
+    #[serde(skip)]
+    results_content_height: f32,
+    /// Offset to return to once a rerun has brought back enough results to reach it.
+    #[serde(skip)]
+    pending_scroll_restore: Option<f32>,
+    /// Tells this tab's widget state, like the results scroll offset, apart from other tabs'.
+    #[serde(skip)]
+    serial: u64,
+    #[serde(skip)]
+    preview_cache: PreviewCache,
+    #[serde(skip)]
     error_message: Option<String>,
+    /// Non-fatal rg stderr lines from the last search, shown until dismissed.
+    #[serde(skip)]
+    warnings: Vec<String>,
+    /// Set to the rg path that failed to spawn, to show install instructions instead of results.
+    #[serde(skip)]
+    rg_not_found: Option<String>,
+    #[serde(skip)]
     search_status: String,
+    #[serde(skip)]
+    search_stats: Option<SearchStats>,
+    /// Result count and time of the last progress status rebuild, to avoid reformatting every frame.
+    #[serde(skip)]
+    status_count: usize,
+    #[serde(skip)]
+    status_updated: Option<Instant>,
+    #[serde(skip)]
+    files_scanned: u64,
     
+    #[serde(skip)]
     search_result_receiver: Option<Receiver<SearchResult>>,
+    #[serde(skip)]
+    cancel_flag: Option<Arc<AtomicBool>>,
+    /// Started with the tab's first search and reused for every later one.
+    #[serde(skip)]
+    search_worker: Option<SearchWorker>,
+    #[serde(skip)]
+    search_started: Option<Instant>,
+    #[serde(skip)]
+    search_elapsed: Option<Duration>,
+    #[serde(skip)]
+    pending_confirmation: Option<(String, PendingAction)>,
+    /// Searches running longer than this are cancelled; `None` disables the limit.
+    time_limit_secs: Option<u32>,
+    /// Matching lines kept per file (`--max-count`); `None` keeps them all.
+    max_count_per_file: Option<u32>,
     
-    case_insensitive: bool,
+    case_mode: CaseMode,
     search_hidden: bool,
     follow_symlinks: bool,
-    globs: String,
+    ignore_level: IgnoreLevel,
+    ignore_files: Vec<String>,
+    binary_mode: BinaryMode,
+    encoding: Option<String>,
+    limit_depth: bool,
+    max_depth: u32,
+    /// `--max-filesize` value such as `10M`; empty for no limit.
+    max_filesize: String,
+    /// Post-filter on match line numbers; see `RgOptions::line_range`.
+    limit_line_range: bool,
+    min_line: u64,
+    max_line: u64,
+    limit_threads: bool,
+    threads: u32,
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+    glob_case_insensitive: bool,
+    types: Vec<String>,
+    types_not: Vec<String>,
+    fixed_string: bool,
+    /// Multi-line query box whose text is searched verbatim (`-F -U`), for pasted snippets.
+    literal_block: bool,
+    whole_word: bool,
+    multiline: bool,
+    /// Preset for patterns spanning whole files: implies `-U --multiline-dotall`.
+    whole_file_pattern: bool,
+    multiline_dotall: bool,
+    pcre2: bool,
+    invert_match: bool,
+    only_matching: bool,
+    count_mode: bool,
+    filename_mode: bool,
+    context_before: u32,
+    context_after: u32,
+    max_results: usize,
+    max_line_chars: usize,
+    /// Extra rg flags, tokenized with `split_args` when a search starts.
+    extra_args: String,
+    #[serde(skip)]
+    saved_search_name: String,
+
+    live_search: bool,
+    /// Repeats the last search whenever the window regains focus.
+    rerun_on_focus: bool,
+    #[serde(skip)]
+    last_search: Option<SearchRequest>,
+    /// The command shown by "Show command", while its window is open.
+    #[serde(skip)]
+    shown_command: Option<String>,
+    /// Input piped in at startup. While set, it is searched instead of `paths`.
+    #[serde(skip)]
+    stdin_input: Option<Arc<Vec<u8>>>,
+    /// What each entry of `paths` pointed at when last checked: `Some(is_dir)`, or `None` if
+    /// it doesn't exist. Cached so the path rows don't stat the file system every frame.
+    #[serde(skip)]
+    path_checks: Vec<(String, Option<bool>)>,
+    #[serde(skip)]
+    path_checked: Option<Instant>,
+    debounce_interval: Duration,
+    #[serde(skip)]
+    last_query_edit: Option<Instant>,
 }
 
-impl Default for MyApp {
+static NEXT_TAB_SERIAL: AtomicU64 = AtomicU64::new(0);
+
+impl Default for SearchTab {
     fn default() -> Self {
         let initial_path = UserDirs::new()
             .and_then(|ud| ud.home_dir().to_str().map(String::from))
             .unwrap_or_else(|| ".".to_string());
 
-        MyApp {
+        SearchTab {
             query: String::new(),
-            path: initial_path,
+            query_error: None,
+            replace: String::new(),
+            paths: vec![initial_path],
             results: Vec::new(),
+            max_row_lines: 0,
+            result_sort: ResultSort::default(),
+            group_by_file: false,
+            relative_paths: false,
+            wrap_lines: false,
+            wrapped_heights: Vec::new(),
+            wrapped_heights_key: (0.0, egui::FontId::default()),
+            show_timings: false,
+            file_timings: HashMap::new(),
+            row_density: RowDensity::default(),
+            result_font: ResultFont::default(),
+            result_font_size: DEFAULT_RESULT_FONT_SIZE,
+            auto_scroll: false,
+            syntax_highlighting: false,
+            show_offsets: false,
+            collapsed_files: HashSet::new(),
+            display_order: Vec::new(),
+            display_order_key: (0, ResultSort::default(), String::new(), false),
+            result_filter: String::new(),
+            block_starts: HashSet::new(),
+            match_frequencies: Vec::new(),
+            match_frequencies_key: 0,
+            extension_counts: HashMap::new(),
+            extension_counts_key: 0,
+            selected_index: None,
+            selection: HashSet::new(),
+            scroll_to_selected: false,
+            results_viewport: (0.0, 0.0),
+            results_content_height: 0.0,
+            pending_scroll_restore: None,
+            serial: NEXT_TAB_SERIAL.fetch_add(1, Ordering::Relaxed),
+            preview_cache: PreviewCache::default(),
             error_message: None,
+            warnings: Vec::new(),
+            rg_not_found: None,
             search_status: "Ready".to_string(),
+            search_stats: None,
+            status_count: 0,
+            status_updated: None,
+            files_scanned: 0,
             search_result_receiver: None,
-            case_insensitive: false,
+            cancel_flag: None,
+            search_worker: None,
+            search_started: None,
+            search_elapsed: None,
+            pending_confirmation: None,
+            time_limit_secs: Some(120),
+            max_count_per_file: None,
+            case_mode: CaseMode::default(),
             search_hidden: false,
             follow_symlinks: false,
-            globs: String::new(),
+            ignore_level: IgnoreLevel::default(),
+            ignore_files: Vec::new(),
+            binary_mode: BinaryMode::default(),
+            encoding: None,
+            limit_depth: false,
+            max_depth: 1,
+            max_filesize: String::new(),
+            limit_line_range: false,
+            min_line: 1,
+            max_line: 100,
+            limit_threads: false,
+            threads: 1,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            glob_case_insensitive: false,
+            types: Vec::new(),
+            types_not: Vec::new(),
+            fixed_string: false,
+            literal_block: false,
+            whole_file_pattern: false,
+            whole_word: false,
+            multiline: false,
+            multiline_dotall: false,
+            pcre2: false,
+            invert_match: false,
+            only_matching: false,
+            count_mode: false,
+            filename_mode: false,
+            context_before: 0,
+            context_after: 0,
+            max_results: 10_000,
+            max_line_chars: 500,
+            extra_args: String::new(),
+            saved_search_name: String::new(),
+            live_search: false,
+            rerun_on_focus: false,
+            last_search: None,
+            shown_command: None,
+            stdin_input: None,
+            path_checks: Vec::new(),
+            path_checked: None,
+            debounce_interval: Duration::from_millis(300),
+            last_query_edit: None,
+        }
+    }
+}
+
+const MAX_QUERY_HISTORY: usize = 50;
+const MAX_RECENT_PATHS: usize = 20;
+/// Channel messages handled per frame per tab; the rest wait for the next frame.
+const MAX_MESSAGES_PER_FRAME: usize = 500;
+/// How often unchanged paths are re-checked, to notice ones created or deleted meanwhile.
+const PATH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+impl SearchTab {
+    /// Overrides the restored state with anything given on the command line.
+    fn apply_launch_args(&mut self, launch: LaunchArgs, settings: &mut Settings) {
+        self.stdin_input = launch.stdin;
+        if let Some(query) = launch.query {
+            self.query = query;
+            self.validate_query();
+        }
+        if !launch.paths.is_empty() {
+            self.paths = launch.paths;
+        }
+        if let Some(case_mode) = launch.case_mode {
+            self.case_mode = case_mode;
+        }
+        self.fixed_string |= launch.fixed_string;
+        self.whole_word |= launch.whole_word;
+        self.search_hidden |= launch.search_hidden;
+        if launch.fixed_string {
+            self.validate_query();
+        }
+        if launch.run && !self.query.trim().is_empty() && self.query_error.is_none() {
+            self.request_search(settings);
+        }
+    }
+
+    /// Checks the query with the same regex syntax rg's default engine uses, so obviously
+    /// broken patterns are caught without spawning rg. Literal and PCRE2 queries are not checked.
+    fn validate_query(&mut self) {
+        self.query_error = if self.fixed_string || self.literal_block || self.pcre2 {
+            None
+        } else {
+            regex_syntax::Parser::new().parse(&self.query).err().map(|e| e.to_string())
+        };
+    }
+
+    /// Rebuilds the "Searching..." status only once the count has moved by 100 results or
+    /// 250ms have passed, so dense searches don't format a new string every frame.
+    fn update_progress_status(&mut self) {
+        let count = self.results.len();
+        if count == self.status_count && self.status_updated.is_some() {
+            return;
+        }
+        let stale = self.status_updated.is_none_or(|updated| updated.elapsed() >= Duration::from_millis(250));
+        if stale || count >= self.status_count + 100 {
+            self.search_status = format!("Searching... Found {} results.", count);
+            self.status_count = count;
+            self.status_updated = Some(Instant::now());
+        }
+    }
+
+    /// Re-checks `paths` when they changed or `PATH_CHECK_INTERVAL` has passed.
+    fn refresh_path_checks(&mut self) {
+        let changed = self.path_checks.len() != self.paths.len()
+            || self.path_checks.iter().zip(&self.paths).any(|((checked, _), path)| checked != path);
+        if !changed && self.path_checked.is_some_and(|checked| checked.elapsed() < PATH_CHECK_INTERVAL) {
+            return;
+        }
+        self.path_checks = self
+            .paths
+            .iter()
+            .map(|path| (path.clone(), std::fs::metadata(path.trim()).ok().map(|meta| meta.is_dir())))
+            .collect();
+        self.path_checked = Some(Instant::now());
+    }
+
+    /// The first non-empty path that doesn't exist; rg would only fail on it.
+    fn missing_path(&self) -> Option<&str> {
+        if self.stdin_input.is_some() {
+            return None;
+        }
+        self.path_checks
+            .iter()
+            .find(|(path, kind)| kind.is_none() && !path.trim().is_empty())
+            .map(|(path, _)| path.trim())
+    }
+
+    /// Starts a search, or asks for confirmation first when it would crawl a huge tree.
+    fn request_search(&mut self, settings: &mut Settings) {
+        self.refresh_path_checks();
+        if let Some(path) = self.missing_path() {
+            self.search_status = format!("Path not found: {}", path);
+            return;
+        }
+        if let Some(message) = settings.rg_too_old() {
+            self.search_status = message;
+            return;
+        }
+        let roots = if self.stdin_input.is_some() { &[][..] } else { &self.paths[..] };
+        match roots.iter().find_map(|p| huge_root_warning(p, self.ignore_level)) {
+            Some(warning) => self.pending_confirmation = Some((warning, PendingAction::Search)),
+            None => self.start_search(settings),
+        }
+    }
+
+    fn show_command_window(&mut self, ctx: &egui::Context) {
+        let Some(command) = &self.shown_command else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("rg command")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                // Read-only but still selectable: edits to a `&str` buffer are discarded.
+                ui.add(egui::TextEdit::multiline(&mut command.as_str()).code_editor().desired_width(f32::INFINITY));
+                if ui.button("Copy").clicked() {
+                    ui.ctx().copy_text(command.clone());
+                }
+            });
+        if !open {
+            self.shown_command = None;
+        }
+    }
+
+    fn show_confirmation(&mut self, ctx: &egui::Context, settings: &mut Settings) {
+        let Some((warning, action)) = self.pending_confirmation.clone() else {
+            return;
+        };
+        let (title, confirm_label) = match action {
+            PendingAction::Search => ("Large search", "Search anyway"),
+            PendingAction::OpenAll => ("Open many files", "Open all"),
+            PendingAction::OpenSelected => ("Open many files", "Open selected"),
+        };
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(warning);
+                ui.horizontal(|ui| {
+                    if ui.button(confirm_label).clicked() {
+                        self.pending_confirmation = None;
+                        match action {
+                            PendingAction::Search => self.start_search(settings),
+                            PendingAction::OpenAll | PendingAction::OpenSelected => self.open_files(action, settings),
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending_confirmation = None;
+                    }
+                });
+            });
+    }
+
+    /// Snapshot of the current options for `run_ripgrep`; fails if the extra args don't parse.
+    fn rg_options(&self, settings: &Settings) -> Result<RgOptions, String> {
+        Ok(RgOptions {
+            rg_path: settings.rg_path.clone(),
+            case_mode: self.case_mode,
+            search_hidden: self.search_hidden,
+            follow_symlinks: self.follow_symlinks,
+            ignore_level: self.ignore_level,
+            ignore_files: self.ignore_files.clone(),
+            binary_mode: self.binary_mode,
+            encoding: self.encoding.clone(),
+            max_depth: self.limit_depth.then_some(self.max_depth),
+            max_filesize: match self.max_filesize.trim() {
+                "" => None,
+                size if is_valid_filesize(size) => Some(size.to_string()),
+                size => return Err(format!("Max file size: '{}' is not a size like 500K, 10M or 1G", size)),
+            },
+            threads: self.limit_threads.then_some(self.threads),
+            include_globs: self.include_globs.clone(),
+            exclude_globs: self.exclude_globs.clone(),
+            glob_case_insensitive: self.glob_case_insensitive,
+            types: self.types.clone(),
+            types_not: self.types_not.clone(),
+            fixed_string: self.fixed_string || self.literal_block,
+            whole_word: self.whole_word,
+            multiline: self.multiline || self.literal_block || self.whole_file_pattern,
+            multiline_dotall: (self.multiline && self.multiline_dotall) || self.whole_file_pattern,
+            pcre2: self.pcre2,
+            invert_match: self.invert_match,
+            only_matching: self.only_matching,
+            count_mode: self.count_mode,
+            filename_mode: self.filename_mode,
+            line_range: self.limit_line_range.then_some((self.min_line, self.max_line)),
+            sort: self.result_sort.rg_sort(),
+            replace: if self.replace.is_empty() { None } else { Some(self.replace.clone()) },
+            context_before: self.context_before,
+            context_after: self.context_after,
+            max_count_per_file: self.max_count_per_file,
+            max_results: self.max_results,
+            max_line_chars: self.max_line_chars,
+            file_timings: self.show_timings,
+            extra_args: split_args(&self.extra_args).map_err(|e| format!("Extra args: {}", e))?,
+        })
+    }
+
+    /// Loads a saved search into the form without running it.
+    fn apply_saved_search(&mut self, saved: SavedSearch) {
+        let options = saved.options;
+        self.query = saved.query;
+        self.paths = saved.paths;
+        self.literal_block = saved.literal_block;
+        self.whole_file_pattern = saved.whole_file_pattern;
+        self.replace = options.replace.unwrap_or_default();
+        // The rg path is an app-wide preference, so a recalled search doesn't change it.
+        self.case_mode = options.case_mode;
+        self.search_hidden = options.search_hidden;
+        self.follow_symlinks = options.follow_symlinks;
+        self.ignore_level = options.ignore_level;
+        self.ignore_files = options.ignore_files;
+        self.binary_mode = options.binary_mode;
+        self.encoding = options.encoding;
+        self.limit_depth = options.max_depth.is_some();
+        if let Some(depth) = options.max_depth {
+            self.max_depth = depth;
+        }
+        self.max_filesize = options.max_filesize.unwrap_or_default();
+        self.limit_threads = options.threads.is_some();
+        if let Some(threads) = options.threads {
+            self.threads = threads;
+        }
+        self.include_globs = options.include_globs;
+        self.exclude_globs = options.exclude_globs;
+        self.glob_case_insensitive = options.glob_case_insensitive;
+        self.types = options.types;
+        self.types_not = options.types_not;
+        self.fixed_string = options.fixed_string;
+        self.whole_word = options.whole_word;
+        self.multiline = options.multiline;
+        self.multiline_dotall = options.multiline_dotall;
+        self.pcre2 = options.pcre2;
+        self.invert_match = options.invert_match;
+        self.only_matching = options.only_matching;
+        self.count_mode = options.count_mode;
+        self.filename_mode = options.filename_mode;
+        self.limit_line_range = options.line_range.is_some();
+        if let Some((min, max)) = options.line_range {
+            self.min_line = min;
+            self.max_line = max;
+        }
+        match options.sort {
+            RgSort::Path => self.result_sort = ResultSort::Path,
+            RgSort::ModifiedNewest => self.result_sort = ResultSort::Modified,
+            RgSort::None if self.result_sort.rg_sort() != RgSort::None => self.result_sort = ResultSort::FileOrder,
+            RgSort::None => {}
+        }
+        self.context_before = options.context_before;
+        self.context_after = options.context_after;
+        self.max_count_per_file = options.max_count_per_file;
+        self.max_results = options.max_results;
+        self.max_line_chars = options.max_line_chars;
+        self.extra_args = join_args(&options.extra_args);
+        self.validate_query();
+    }
+
+    /// Stores the current form under `saved_search_name`, replacing a preset of the same name.
+    fn save_current_search(&mut self, settings: &mut Settings) {
+        let name = self.saved_search_name.trim().to_string();
+        let options = match self.rg_options(settings) {
+            Ok(options) => options,
+            Err(e) => {
+                self.error_message = Some(e);
+                return;
+            }
+        };
+        let saved = SavedSearch {
+            name: name.clone(),
+            query: self.query.clone(),
+            paths: self.paths.clone(),
+            options,
+            literal_block: self.literal_block,
+            whole_file_pattern: self.whole_file_pattern,
+        };
+        match settings.saved_searches.iter_mut().find(|s| s.name == name) {
+            Some(existing) => *existing = saved,
+            None => settings.saved_searches.push(saved),
+        }
+    }
+
+    /// The search the form currently describes.
+    fn search_request(&self, settings: &Settings) -> Result<SearchRequest, String> {
+        let options = self.rg_options(settings)?;
+        // Pasted text may carry Windows line endings; rg's -U matches `\n` against file content.
+        let query = if self.literal_block { self.query.replace("\r\n", "\n") } else { self.query.clone() };
+        if let Some(input) = &self.stdin_input {
+            return Ok(SearchRequest { query, paths: vec!["-".to_string()], options, input: Some(input.clone()) });
+        }
+        let paths: Vec<String> = self.paths.iter().map(|p| p.trim()).filter(|p| !p.is_empty()).map(String::from).collect();
+        Ok(SearchRequest { query, paths, options, input: None })
+    }
+
+    fn start_search(&mut self, settings: &mut Settings) {
+        let request = match self.search_request(settings) {
+            Ok(request) => request,
+            Err(e) => {
+                self.search_status = format!("Search failed: {}", e);
+                self.error_message = Some(e);
+                return;
+            }
+        };
+        settings.remember_query(&self.query);
+        if request.input.is_none() {
+            settings.remember_paths(&request.paths);
+        }
+        self.run_search(request);
+    }
+
+    /// Opens a window with the rg command the current form would run, without running it.
+    fn show_command(&mut self, settings: &Settings) {
+        match self.search_request(settings) {
+            Ok(request) => {
+                let args = build_rg_args(&request.query, &request.paths, &request.options);
+                self.shown_command = Some(command_line(&request.options.rg_path, &args));
+            }
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
+    /// Repeats the last search as it was run, regardless of edits to the form since.
+    fn rerun_search(&mut self) {
+        if let Some(request) = self.last_search.clone() {
+            self.cancel_search();
+            // The same search mostly finds the same results, so pick up reading where it was.
+            let (offset, _) = self.results_viewport;
+            self.run_search(request);
+            self.pending_scroll_restore = (offset > 0.0).then_some(offset);
+        }
+    }
+
+    /// Drops the results of the previous search and everything derived from them.
+    fn reset_results(&mut self) {
+        self.results.clear();
+        self.max_row_lines = 0;
+        self.wrapped_heights.clear();
+        self.selected_index = None;
+        self.selection.clear();
+        self.display_order.clear();
+        self.block_starts.clear();
+        self.display_order_key = (0, self.result_sort, self.result_filter.clone(), self.group_by_file);
+        self.preview_cache.clear();
+        self.match_frequencies.clear();
+        self.match_frequencies_key = 0;
+        self.extension_counts.clear();
+        self.extension_counts_key = 0;
+        self.error_message = None;
+        self.rg_not_found = None;
+        self.warnings.clear();
+        self.search_stats = None;
+        self.status_count = 0;
+        self.status_updated = None;
+        self.files_scanned = 0;
+        self.file_timings.clear();
+    }
+
+    /// Empties the results view without starting a new search.
+    fn clear_results(&mut self) {
+        self.result_filter.clear();
+        self.reset_results();
+        self.search_elapsed = None;
+        self.search_status = "Ready".to_string();
+    }
+
+    fn run_search(&mut self, request: SearchRequest) {
+        self.last_search = Some(request.clone());
+        self.reset_results();
+        if let Some(input) = &request.input {
+            self.preview_cache.insert(STDIN_PATH, input);
+        }
+        self.search_status = "Starting search...".to_string();
+
+        // A search still running would otherwise keep the worker busy until it finished.
+        if let Some(previous) = self.cancel_flag.take() {
+            previous.store(true, Ordering::Relaxed);
+        }
+        let (tx, rx) = unbounded::<SearchResult>();
+        self.search_result_receiver = Some(rx);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.cancel_flag = Some(cancel.clone());
+        self.search_started = Some(Instant::now());
+        self.search_elapsed = None;
+
+        self.search_worker.get_or_insert_with(SearchWorker::spawn).submit(request, tx, cancel);
+    }
+
+    /// Height of one result row, sized for the result with the most text lines so that
+    /// `ScrollArea::show_rows` can lay out only the visible rows.
+    fn result_row_height(&self, ui: &egui::Ui) -> f32 {
+        if self.row_density == RowDensity::Compact {
+            return ui.text_style_height(&egui::TextStyle::Monospace).max(ui.text_style_height(&egui::TextStyle::Body));
+        }
+        let spacing = ui.spacing().item_spacing.y;
+        let header = ui.text_style_height(&egui::TextStyle::Body).max(ui.spacing().interact_size.y);
+        let line = ui.text_style_height(&egui::TextStyle::Monospace);
+        let lines = self.max_row_lines.max(1);
+        header + lines as f32 * (line + spacing) + egui::Frame::group(ui.style()).inner_margin.sum().y
+    }
+
+    /// Wrapped rows vary in height, so they get their own `wrapped_heights` instead of sharing
+    /// `result_row_height`.
+    fn wraps_rows(&self) -> bool {
+        self.wrap_lines && self.row_density == RowDensity::Comfortable
+    }
+
+    /// Height of a comfortable row whose text wraps at `wrap_width`.
+    fn wrapped_row_height(&self, ui: &egui::Ui, m: &GuiMatch, wrap_width: f32) -> f32 {
+        let spacing = ui.spacing().item_spacing.y;
+        let header = ui.text_style_height(&egui::TextStyle::Body).max(ui.spacing().interact_size.y);
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let text_height = |text: String| {
+            ui.fonts(|f| f.layout(text, font_id.clone(), egui::Color32::PLACEHOLDER, wrap_width).size().y) + spacing
+        };
+        let mut height = header + egui::Frame::group(ui.style()).inner_margin.sum().y;
+        for c in m.context_before.iter().chain(&m.context_after) {
+            height += text_height(format!("{}- {}", c.line_number, c.text));
+        }
+        if m.binary_offset.is_some() {
+            height += text_height(m.line_text.clone());
+        } else if m.match_count.is_none() && !m.name_match {
+            let mut job = self.line_layout(ui, &m.path, &m.line_text, &m.submatches);
+            job.wrap.max_width = wrap_width;
+            height += ui.fonts(|f| f.layout_job(job)).size().y + spacing;
+        }
+        height
+    }
+
+    /// Measures the rows that arrived since the last frame, or all of them again once the wrap
+    /// width or result font changed.
+    fn update_wrapped_heights(&mut self, ui: &egui::Ui, wrap_width: f32) {
+        let key = (wrap_width, egui::TextStyle::Monospace.resolve(ui.style()));
+        if self.wrapped_heights_key != key {
+            self.wrapped_heights.clear();
+            self.wrapped_heights_key = key;
+        }
+        let measured: Vec<f32> = self.results[self.wrapped_heights.len()..]
+            .iter()
+            .map(|m| self.wrapped_row_height(ui, m, wrap_width))
+            .collect();
+        self.wrapped_heights.extend(measured);
+    }
+
+    /// `path` relative to the search root it falls under when "Relative paths" is on. Paths
+    /// outside every root, and a root that is itself the matched file, stay as rg reported them.
+    fn display_path<'a>(&self, path: &'a str) -> &'a str {
+        let Some(search) = self.last_search.as_ref().filter(|_| self.relative_paths) else {
+            return path;
+        };
+        // The innermost root wins when roots are nested.
+        search
+            .paths
+            .iter()
+            .filter_map(|root| Path::new(path).strip_prefix(root.trim()).ok())
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .min_by_key(|relative| relative.as_os_str().len())
+            .and_then(|relative| relative.to_str())
+            .unwrap_or(path)
+    }
+
+    /// Draws one result row `row_height` tall and records a click or context-menu choice in
+    /// `action`.
+    fn show_result_row(&self, ui: &mut egui::Ui, settings: &Settings, index: usize, row_height: f32, action: &mut Option<RowAction>) -> egui::Response {
+        let m = &self.results[index];
+        let path = self.display_path(&m.path);
+        // Allocate the whole row first so buttons drawn inside it take click priority.
+        let (row_rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), row_height), egui::Sense::click());
+        let response = response.on_hover_cursor(egui::CursorIcon::PointingHand);
+        if self.block_starts.contains(&index) {
+            let y = row_rect.top() - ui.spacing().item_spacing.y / 2.0;
+            let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+            ui.painter().extend(egui::Shape::dashed_line(&[egui::pos2(row_rect.left(), y), egui::pos2(row_rect.right(), y)], stroke, 4.0, 3.0));
+        }
+        if self.row_density == RowDensity::Compact {
+            if self.selection.contains(&index) {
+                ui.painter().rect_filled(row_rect, 2.0, ui.visuals().selection.bg_fill.gamma_multiply(0.4));
+            }
+            ui.allocate_ui_at_rect(row_rect, |ui| {
+                ui.style_mut().wrap = Some(false);
+                ui.horizontal(|ui| {
+                    if m.name_match {
+                        if location_link(ui, name_match_layout(ui, m, path)) {
+                            *action = Some(RowAction::Open(index));
+                        }
+                        return;
+                    }
+                    let location = match m.match_count {
+                        Some(count) => format!("{}: {} matches", path, count),
+                        None if m.binary_offset.is_some() => format!("{}:", path),
+                        None => format!("{}:{}:", path, m.line_number),
+                    };
+                    let location = match m.absolute_offset.filter(|_| self.show_offsets) {
+                        Some(offset) => format!("{} @{}:", location.trim_end_matches(':'), offset),
+                        None => location,
+                    };
+                    if location_link(ui, egui::RichText::new(location).monospace().color(ui.visuals().weak_text_color())) {
+                        *action = Some(RowAction::Open(index));
+                    }
+                    if m.match_count.is_none() {
+                        // Only the first line of a multiline match fits in a compact row.
+                        let first_line = m.line_text.lines().next().unwrap_or_default();
+                        ui.add(egui::Label::new(self.line_layout(ui, &m.path, first_line, &m.submatches)).selectable(true));
+                    }
+                });
+            });
+        } else {
+            let mut frame = egui::Frame::group(ui.style());
+            if self.selection.contains(&index) {
+                frame = frame
+                    .fill(ui.visuals().selection.bg_fill.gamma_multiply(0.4))
+                    .stroke(ui.visuals().selection.stroke);
+            }
+            ui.allocate_ui_at_rect(row_rect, |ui| {
+                frame.show(ui, |ui| {
+                     ui.style_mut().wrap = Some(false);
+                     ui.horizontal(|ui| {
+                         let header: egui::WidgetText = if m.name_match {
+                             name_match_layout(ui, m, path).into()
+                         } else if let Some(count) = m.match_count {
+                             egui::RichText::new(format!("{}: {} matches", path, count)).strong().into()
+                         } else {
+                             let location = match m.column {
+                                 _ if m.binary_offset.is_some() => path.to_string(),
+                                 Some(column) => format!("{}:{}:{}", path, m.line_number, column),
+                                 None => format!("{}:{}", path, m.line_number),
+                             };
+                             match m.absolute_offset.filter(|_| self.show_offsets) {
+                                 Some(offset) => egui::RichText::new(format!("{} @{}", location, offset)).strong().into(),
+                                 None => egui::RichText::new(location).strong().into(),
+                             }
+                         };
+                         if location_link(ui, header) {
+                             *action = Some(RowAction::Open(index));
+                         }
+                         if !settings.custom_command.trim().is_empty()
+                             && ui.small_button("▶").on_hover_text(format!("Run: {}", settings.custom_command)).clicked()
+                         {
+                             *action = Some(RowAction::RunCommand(index));
+                         }
+                         if ui.small_button("Copy").on_hover_text("Copy path:line (Shift-click copies the line text)").clicked() {
+                             if ui.input(|i| i.modifiers.shift) {
+                                 ui.ctx().copy_text(m.line_text.clone());
+                             } else if m.name_match {
+                                 ui.ctx().copy_text(m.path.clone());
+                             } else {
+                                 ui.ctx().copy_text(format!("{}:{}", m.path, m.line_number));
+                             }
+                         }
+                     });
+                     // Only the text below the header wraps; `wrapped_row_height` measures it the same way.
+                     ui.style_mut().wrap = Some(self.wrap_lines);
+                     let context_color = ui.visuals().weak_text_color();
+                     for c in &m.context_before {
+                         ui.label(egui::RichText::new(format!("{}- {}", c.line_number, c.text)).monospace().color(context_color));
+                     }
+                     if m.match_count.is_some() || m.name_match {
+                         // Count and file name rows are fully described by their header.
+                     } else if m.binary_offset.is_some() {
+                         ui.label(egui::RichText::new(&m.line_text).monospace().italics().color(context_color));
+                     } else {
+                         ui.add(egui::Label::new(self.line_layout(ui, &m.path, &m.line_text, &m.submatches)).selectable(true));
+                     }
+                     for c in &m.context_after {
+                         ui.label(egui::RichText::new(format!("{}- {}", c.line_number, c.text)).monospace().color(context_color));
+                     }
+                });
+            });
+        }
+        if response.double_clicked() {
+            *action = Some(RowAction::Open(index));
+        } else if response.clicked() {
+            *action = Some(RowAction::Select(index));
+        }
+        response.context_menu(|ui| {
+            if ui.button("Open file").clicked() {
+                *action = Some(RowAction::Open(index));
+                ui.close_menu();
+            }
+            if ui.button("Open containing folder").clicked() {
+                *action = Some(RowAction::Reveal(index));
+                ui.close_menu();
+            }
+            if ui.button("Copy path").clicked() {
+                ui.ctx().copy_text(m.path.clone());
+                ui.close_menu();
+            }
+            if let Some(offset) = m.absolute_offset
+                && ui.button("Copy byte offset").on_hover_text(format!("{} bytes from the start of the file", offset)).clicked()
+            {
+                ui.ctx().copy_text(offset.to_string());
+                ui.close_menu();
+            }
+        });
+        response
+    }
+
+    /// Each distinct file among `indices` with the line and byte offset of its first match, in
+    /// that order.
+    fn result_files(&self, indices: impl IntoIterator<Item = usize>) -> Vec<(String, u64, Option<u64>)> {
+        let mut seen = HashSet::new();
+        indices
+            .into_iter()
+            .map(|i| &self.results[i])
+            .filter(|m| seen.insert(m.path.as_str()))
+            .map(|m| (m.path.clone(), m.line_number.max(1), m.absolute_offset))
+            .collect()
+    }
+
+    /// The selected results that are currently shown, in display order.
+    fn selected_results(&self) -> Vec<usize> {
+        self.display_order.iter().copied().filter(|i| self.selection.contains(i)).collect()
+    }
+
+    /// Files for "Open all" (`OpenAll`) or "Open selected" (`OpenSelected`).
+    fn files_to_open(&self, action: PendingAction) -> Vec<(String, u64, Option<u64>)> {
+        match action {
+            PendingAction::OpenSelected => self.result_files(self.selected_results()),
+            _ => self.result_files(0..self.results.len()),
+        }
+    }
+
+    fn request_open_files(&mut self, action: PendingAction, settings: &Settings) {
+        let count = self.files_to_open(action).len();
+        if count > OPEN_ALL_CONFIRM_THRESHOLD {
+            let warning = format!("This will open {} files in your editor.", count);
+            self.pending_confirmation = Some((warning, action));
+        } else {
+            self.open_files(action, settings);
+        }
+    }
+
+    fn open_files(&mut self, action: PendingAction, settings: &Settings) {
+        if let Err(e) = open_all_in_editor(&settings.editor_command, &self.files_to_open(action)) {
+            self.error_message = Some(e);
+        }
+    }
+
+    /// Applies a row click: Ctrl/Cmd toggles the row, Shift selects the shown rows from the
+    /// focused one to it, and a plain click selects just that row.
+    fn click_row(&mut self, index: usize, modifiers: egui::Modifiers) {
+        let anchor = self.selected_row();
+        let clicked = self.display_order.iter().position(|&i| i == index);
+        if modifiers.command {
+            if !self.selection.remove(&index) {
+                self.selection.insert(index);
+            }
+            self.selected_index = Some(index);
+        } else if modifiers.shift
+            && let (Some(anchor), Some(clicked)) = (anchor, clicked)
+        {
+            self.selection = self.display_order[anchor.min(clicked)..=anchor.max(clicked)].iter().copied().collect();
+        } else {
+            self.select_only(index);
+        }
+    }
+
+    fn select_only(&mut self, index: usize) {
+        self.selected_index = Some(index);
+        self.selection = HashSet::from([index]);
+    }
+
+    /// Layout for a match line: syntax colored when enabled and the extension is known,
+    /// otherwise plain, with the submatch ranges highlighted either way.
+    fn line_layout(&self, ui: &egui::Ui, path: &str, text: &str, submatches: &[(usize, usize)]) -> LayoutJob {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .filter(|e| SYNTAX_EXTENSIONS.contains(&e.as_str()));
+        match extension {
+            Some(extension) if self.syntax_highlighting => syntax_highlighted_line(ui, text, submatches, &extension),
+            _ => highlighted_line(ui, text, submatches),
+        }
+    }
+
+    fn open_result(&mut self, index: usize, settings: &Settings) {
+        let Some(m) = self.results.get(index) else {
+            return;
+        };
+        if m.path == STDIN_PATH {
+            self.error_message = Some("Results from piped input have no file to open.".to_string());
+            return;
+        }
+        // File name, count and binary rows have no line; open those files at the top.
+        if let Err(e) = open_in_editor(&settings.editor_command, &m.path, m.line_number.max(1), m.column, m.absolute_offset) {
+            self.error_message = Some(e);
+        }
+    }
+
+    /// Rebuilds `display_order` when results arrive or the sort mode or filter changes. Results
+    /// are never reordered in place, so indices held elsewhere (like the selection) stay valid.
+    fn refresh_display_order(&mut self) {
+        let (len, sort, filter, grouped) = &self.display_order_key;
+        if *len == self.results.len()
+            && *sort == self.result_sort
+            && *filter == self.result_filter
+            && *grouped == self.group_by_file
+        {
+            return;
+        }
+        let results = &self.results;
+        let needle = self.result_filter.trim().to_lowercase();
+        self.display_order = (0..results.len())
+            .filter(|&i| {
+                needle.is_empty()
+                    || results[i].path.to_lowercase().contains(&needle)
+                    || results[i].line_text.to_lowercase().contains(&needle)
+            })
+            .collect();
+        match self.result_sort {
+            ResultSort::FileOrder | ResultSort::Modified => {}
+            ResultSort::Path => self.display_order.sort_by(|&a, &b| {
+                (&results[a].path, results[a].line_number).cmp(&(&results[b].path, results[b].line_number))
+            }),
+            ResultSort::LineNumber => self.display_order.sort_by(|&a, &b| {
+                (results[a].line_number, &results[a].path).cmp(&(results[b].line_number, &results[b].path))
+            }),
+        }
+        if self.group_by_file {
+            // Pull each file's rows together, keeping files in order of first appearance, so
+            // groups are contiguous runs and keyboard navigation follows what's on screen.
+            let mut first_seen: HashMap<&str, usize> = HashMap::new();
+            for (pos, &i) in self.display_order.iter().enumerate() {
+                first_seen.entry(results[i].path.as_str()).or_insert(pos);
+            }
+            self.display_order.sort_by_key(|&i| first_seen[results[i].path.as_str()]);
+        }
+        // Like rg's `--` context separator, which its JSON output leaves out.
+        let shows_context = self.last_search.as_ref().is_some_and(|s| s.options.context_before + s.options.context_after > 0);
+        self.block_starts = if shows_context {
+            self.display_order
+                .windows(2)
+                .filter(|pair| starts_new_block(&results[pair[0]], &results[pair[1]]))
+                .map(|pair| pair[1])
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        self.display_order_key = (self.results.len(), self.result_sort, self.result_filter.clone(), self.group_by_file);
+    }
+
+    /// Counts the results that arrived since the last call into `extension_counts`.
+    fn refresh_extension_counts(&mut self) {
+        for m in &self.results[self.extension_counts_key..] {
+            let extension = match std::path::Path::new(&m.path).extension() {
+                Some(extension) => format!(".{}", extension.to_string_lossy()),
+                None => "(no extension)".to_string(),
+            };
+            *self.extension_counts.entry(extension).or_default() += m.match_count.unwrap_or(1);
+        }
+        self.extension_counts_key = self.results.len();
+    }
+
+    fn refresh_match_frequencies(&mut self) {
+        if self.match_frequencies_key == self.results.len() {
+            return;
+        }
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for m in self.results.iter().filter(|m| m.match_count.is_none() && m.binary_offset.is_none() && !m.name_match) {
+            *counts.entry(m.line_text.as_str()).or_default() += 1;
+        }
+        let mut frequencies: Vec<(String, usize)> = counts.into_iter().map(|(text, n)| (text.to_string(), n)).collect();
+        frequencies.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self.match_frequencies = frequencies;
+        self.match_frequencies_key = self.results.len();
+    }
+
+    /// The results list in drawing order: with grouping on, each file's header followed by its
+    /// rows unless the file is collapsed.
+    fn result_list_items(&self) -> Vec<ListItem> {
+        if !self.group_by_file {
+            return self.display_order.iter().map(|&index| ListItem::Row(index)).collect();
+        }
+        let mut items = Vec::new();
+        let mut start = 0;
+        while start < self.display_order.len() {
+            let path = &self.results[self.display_order[start]].path;
+            let end = self.display_order[start..]
+                .iter()
+                .position(|&i| self.results[i].path != *path)
+                .map_or(self.display_order.len(), |n| start + n);
+            items.push(ListItem::Header { start, end });
+            if !self.collapsed_files.contains(path) {
+                items.extend(self.display_order[start..end].iter().map(|&index| ListItem::Row(index)));
+            }
+            start = end;
+        }
+        items
+    }
+
+    /// Draws the header of the file group `display_order[start..end]`; returns whether it
+    /// was clicked to collapse or expand the group.
+    fn show_group_header(&self, ui: &mut egui::Ui, start: usize, end: usize, height: f32) -> bool {
+        let path = &self.results[self.display_order[start]].path;
+        let matches: u64 = self.display_order[start..end]
+            .iter()
+            .map(|&i| self.results[i].match_count.unwrap_or(1))
+            .sum();
+        let icon = if self.collapsed_files.contains(path) { "⏵" } else { "⏷" };
+        let label = match self.file_timings.get(path).filter(|_| self.show_timings) {
+            Some(elapsed) => format!("{} {} ({} matches, {:.1?})", icon, self.display_path(path), matches, elapsed),
+            None => format!("{} {} ({} matches)", icon, self.display_path(path), matches),
+        };
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), height), egui::Sense::click());
+        let color = if response.hovered() { ui.visuals().strong_text_color() } else { ui.visuals().text_color() };
+        ui.painter().text(rect.left_center(), egui::Align2::LEFT_CENTER, label, egui::TextStyle::Body.resolve(ui.style()), color);
+        response.on_hover_cursor(egui::CursorIcon::PointingHand).clicked()
+    }
+
+    /// Position of the selected result within `display_order`.
+    fn selected_row(&self) -> Option<usize> {
+        self.selected_index.and_then(|i| self.display_order.iter().position(|&j| j == i))
+    }
+
+    /// Up/Down move the selection, F3/Shift+F3 step to the next/previous match (wrapping
+    /// across file groups), and Enter opens it, unless a text field has keyboard focus.
+    fn handle_result_keys(&mut self, ctx: &egui::Context, settings: &Settings) {
+        if self.display_order.is_empty() {
+            return;
+        }
+        // F3 is not a text-editing key, so it keeps working while the query field has focus.
+        let (next, previous) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::F3),
+                i.consume_key(egui::Modifiers::SHIFT, egui::Key::F3),
+            )
+        });
+        if next {
+            self.step_match(true);
+        }
+        if previous {
+            self.step_match(false);
+        }
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (up, down, enter) = ctx.input(|i| {
+            (i.key_pressed(egui::Key::ArrowUp), i.key_pressed(egui::Key::ArrowDown), i.key_pressed(egui::Key::Enter))
+        });
+        let last = self.display_order.len() - 1;
+        let current_row = self.selected_row();
+        if down {
+            self.select_row(current_row.map_or(0, |r| (r + 1).min(last)));
+        }
+        if up {
+            self.select_row(current_row.map_or(0, |r| r.saturating_sub(1)));
+        }
+        if enter && let Some(index) = self.selected_index {
+            self.open_result(index, settings);
+        }
+    }
+
+    /// Selects the next or previous shown result, wrapping around at either end.
+    fn step_match(&mut self, forward: bool) {
+        let len = self.display_order.len();
+        if len == 0 {
+            return;
+        }
+        let row = match self.selected_row() {
+            Some(r) if forward => (r + 1) % len,
+            Some(r) => (r + len - 1) % len,
+            None if forward => 0,
+            None => len - 1,
+        };
+        self.select_row(row);
+    }
+
+    fn select_row(&mut self, row: usize) {
+        self.select_only(self.display_order[row]);
+        self.scroll_to_selected = true;
+    }
+
+    /// Detaches from the running search and freezes the elapsed timer.
+    fn end_search(&mut self) {
+        self.search_result_receiver = None;
+        self.cancel_flag = None;
+        if let Some(started) = self.search_started.take() {
+            self.search_elapsed = Some(started.elapsed());
+        }
+    }
+
+    /// Number of lines shown above and below the selected match in the preview pane.
+    const PREVIEW_RADIUS: usize = 10;
+
+    fn show_preview(&mut self, ctx: &egui::Context) {
+        let Some(m) = self.selected_index.and_then(|i| self.results.get(i)) else {
+            return;
+        };
+        let (path, line_number) = (m.path.clone(), m.line_number as usize);
+        egui::SidePanel::right("preview").resizable(true).default_width(360.0).show(ctx, |ui| {
+            ui.strong(format!("{}:{}", path, line_number));
+            ui.separator();
+            match self.preview_cache.lines(&path) {
+                Ok(lines) => {
+                    let first = line_number.saturating_sub(Self::PREVIEW_RADIUS).max(1);
+                    let last = (line_number + Self::PREVIEW_RADIUS).min(lines.len());
+                    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                        for (n, line) in lines.iter().enumerate().take(last).skip(first - 1).map(|(i, l)| (i + 1, l)) {
+                            let text = egui::RichText::new(format!("{:>5} {}", n, line)).monospace();
+                            if n == line_number {
+                                ui.label(text.background_color(ui.visuals().selection.bg_fill.gamma_multiply(0.4)));
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+                }
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+            }
+        });
+    }
+
+    fn cancel_search(&mut self) {
+        if let Some(cancel) = self.cancel_flag.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        self.end_search();
+    }
+
+    fn handle_search_result(&mut self, search_result: SearchResult) {
+        match search_result {
+            SearchResult::Batch(matches) => { 
+                for m in &matches {
+                    // Multiline matches span several text lines, so count them too.
+                    let row_lines = m.line_text.lines().count().max(1) + m.context_before.len() + m.context_after.len();
+                    self.max_row_lines = self.max_row_lines.max(row_lines);
+                }
+                self.results.extend(matches); 
+                self.update_progress_status();
+            }
+            SearchResult::Warnings(warnings) => {
+                self.warnings.extend(warnings);
+            }
+            SearchResult::Progress { files_scanned } => {
+                self.files_scanned = files_scanned;
+            }
+            SearchResult::FileTimings(timings) => {
+                self.file_timings.extend(timings);
+            }
+            SearchResult::Stats(stats) => {
+                self.search_stats = Some(stats);
+            }
+            SearchResult::Done => {
+                self.search_status = if self.results.is_empty() {
+                    "Search finished. No matches found.".to_string()
+                } else {
+                    format!("Search finished. Found {} results.", self.results.len())
+                };
+                self.end_search();
+            }
+            SearchResult::Capped(limit) => {
+                self.search_status = format!("Showing first {} results (capped)", limit);
+                self.end_search();
+            }
+            SearchResult::Error(e) => {
+                self.error_message = Some(e.clone());
+                self.search_status = format!("Search failed: {}", e);
+                self.end_search();
+            }
+            SearchResult::RgNotFound(rg_path) => {
+                self.search_status = format!("Search failed: '{}' not found", rg_path);
+                self.rg_not_found = Some(rg_path);
+                self.end_search();
+            }
         }
     }
-}
 
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        
-        if let Some(rx) = &self.search_result_receiver {
+    /// Takes in search messages and runs timers. Called every frame for every tab, so
+    /// searches in background tabs keep going.
+    fn poll(&mut self, ctx: &egui::Context, settings: &mut Settings) {
+        // A burst of messages is spread over several frames rather than stalling one. Done and
+        // errors are queued after the matches, so they're reached once the backlog drains.
+        for _ in 0..MAX_MESSAGES_PER_FRAME {
+            let Some(rx) = &self.search_result_receiver else {
+                break;
+            };
             match rx.try_recv() {
-                Ok(search_result) => match search_result {
-                    SearchResult::Match(gui_match) => { 
-                        self.results.push(gui_match); 
-                        self.search_status = format!("Found {} results...", self.results.len());
-                    }
-                    SearchResult::Done => {
-                        self.search_status = format!("Search finished. Found {} results.", self.results.len());
-                        self.search_result_receiver = None; 
-                    }
-                    SearchResult::Error(e) => {
-                        self.error_message = Some(e.clone());
-                        self.search_status = format!("Search failed: {}", e);
-                        self.search_result_receiver = None; 
-                    }
-                },
+                Ok(search_result) => self.handle_search_result(search_result),
                 Err(TryRecvError::Empty) => {
-                    
-                    self.search_status = format!("Searching... Found {} results.", self.results.len());
+                    self.update_progress_status();
+                    break;
                 }
                 Err(TryRecvError::Disconnected) => {
-                    
                     self.error_message = Some("Search thread disconnected unexpectedly.".to_string());
                     self.search_status = "Error: Search thread disconnected.".to_string();
-                    self.search_result_receiver = None;
+                    self.end_search();
+                    break;
                 }
             }
         }
 
+        if let Some(edited_at) = self.last_query_edit {
+            if !self.live_search || self.query.trim().is_empty() || self.query_error.is_some() {
+                self.last_query_edit = None;
+            } else if edited_at.elapsed() >= self.debounce_interval {
+                self.last_query_edit = None;
+                self.cancel_search();
+                self.request_search(settings);
+            } else {
+                ctx.request_repaint_after(self.debounce_interval.saturating_sub(edited_at.elapsed()));
+            }
+        }
+
+        if let (Some(limit), Some(started)) = (self.time_limit_secs, self.search_started)
+            && started.elapsed() >= Duration::from_secs(u64::from(limit))
+        {
+            self.cancel_search();
+            self.search_status = format!("Search stopped after the {}s time limit. Found {} results.", limit, self.results.len());
+        }
+
+        if self.search_result_receiver.is_some() {
+             ctx.request_repaint();
+        }
+    }
+
+    fn show(&mut self, ctx: &egui::Context, settings: &mut Settings) {
+        self.refresh_path_checks();
+
+        let regained_focus = ctx.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::WindowFocused(true))));
+        if regained_focus && self.rerun_on_focus && self.search_result_receiver.is_none() {
+            self.rerun_search();
+        }
+
+        self.refresh_display_order();
+        self.handle_result_keys(ctx, settings);
+        self.show_confirmation(ctx, settings);
+        self.show_command_window(ctx);
+        self.show_preview(ctx);
+
+        // Ctrl+L (Cmd+L on macOS) focuses the query like a browser address bar; so does `/`
+        // when no other text field is taking keystrokes.
+        let focus_query = ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::L))
+            || (!ctx.wants_keyboard_input() && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Slash)));
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Ripgrep GUI");
+            ui.horizontal(|ui| {
+                ui.heading("Ripgrep GUI");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let dark_mode = ui.visuals().dark_mode;
+                    let (icon, hint) = if dark_mode { ("☀", "Switch to light theme") } else { ("🌙", "Switch to dark theme") };
+                    if ui.button(icon).on_hover_text(hint).clicked() {
+                        settings.dark_mode = Some(!dark_mode);
+                        ctx.set_visuals(if dark_mode { egui::Visuals::light() } else { egui::Visuals::dark() });
+                    }
+                    if ui.button("ℹ").on_hover_text("About").clicked() {
+                        settings.show_about = !settings.show_about;
+                    }
+                });
+            });
             ui.separator();
 
             
             ui.horizontal(|ui| {
                 ui.label("Search:");
-                ui.text_edit_singleline(&mut self.query);
+                let mut query_edit = if self.literal_block {
+                    egui::TextEdit::multiline(&mut self.query).desired_rows(4).code_editor()
+                } else {
+                    egui::TextEdit::singleline(&mut self.query)
+                };
+                if self.query_error.is_some() {
+                    query_edit = query_edit.text_color(egui::Color32::RED);
+                }
+                let mut query_response = ui.add(query_edit);
+                if let Some(err) = &self.query_error {
+                    query_response = query_response.on_hover_text(egui::RichText::new(err).monospace());
+                }
+                if query_response.changed() {
+                    self.last_query_edit = Some(Instant::now());
+                    self.validate_query();
+                }
+                // egui drops focus on Escape before widgets run, so this sees it as just lost.
+                if query_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.query.clear();
+                    self.validate_query();
+                    query_response.request_focus();
+                }
+                if focus_query {
+                    query_response.request_focus();
+                }
+                let mut picked_query = None;
+                ui.add_enabled_ui(!settings.query_history.is_empty(), |ui| {
+                    egui::ComboBox::from_id_source(("query_history", self.serial))
+                        .selected_text("History")
+                        .show_ui(ui, |ui| {
+                            for q in &settings.query_history {
+                                if ui.selectable_label(false, q).clicked() {
+                                    picked_query = Some(q.clone());
+                                }
+                            }
+                        });
+                });
+                if ui.checkbox(&mut self.literal_block, "Literal block")
+                    .on_hover_text("Multi-line query searched verbatim, newlines included (-F -U). Handy for pasted snippets.")
+                    .changed()
+                {
+                    self.validate_query();
+                }
+                if let Some(q) = picked_query {
+                    self.query = q;
+                    self.validate_query();
+                    // Only matters when live search is on; otherwise the query just sits in the box.
+                    self.last_query_edit = Some(Instant::now());
+                }
             });
             ui.horizontal(|ui| {
-                ui.label("Path:");
-                ui.text_edit_singleline(&mut self.path);
-                if ui.button("Browse...").clicked() {
-                    
-                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
-                         self.path = path.display().to_string();
-                    }
+                ui.label("Saved searches:");
+                let mut picked = None;
+                ui.add_enabled_ui(!settings.saved_searches.is_empty(), |ui| {
+                    egui::ComboBox::from_id_source(("saved_searches", self.serial))
+                        .selected_text("Recall")
+                        .show_ui(ui, |ui| {
+                            for saved in &settings.saved_searches {
+                                if ui.selectable_label(false, &saved.name).clicked() {
+                                    picked = Some(saved.clone());
+                                }
+                            }
+                        });
+                });
+                if let Some(saved) = picked {
+                    self.saved_search_name = saved.name.clone();
+                    self.apply_saved_search(saved);
+                }
+                ui.add(egui::TextEdit::singleline(&mut self.saved_search_name).hint_text("name").desired_width(120.0));
+                let name = self.saved_search_name.trim();
+                if ui.add_enabled(!name.is_empty(), egui::Button::new("Save search"))
+                    .on_hover_text("Save the query, paths and all options under this name")
+                    .clicked()
+                {
+                    self.save_current_search(settings);
+                }
+                let exists = settings.saved_searches.iter().any(|s| s.name == self.saved_search_name.trim());
+                if ui.add_enabled(exists, egui::Button::new("Delete")).clicked() {
+                    let name = self.saved_search_name.trim().to_string();
+                    settings.saved_searches.retain(|s| s.name != name);
                 }
             });
+            ui.horizontal(|ui| {
+                ui.label("Replace:");
+                ui.add(egui::TextEdit::singleline(&mut self.replace).hint_text("preview only, files are not modified"))
+                    .on_hover_text("Passes -r to rg to show what each matched line would look like after substitution. Capture groups like $1 are supported.");
+            });
+            if self.stdin_input.is_some() {
+                ui.horizontal(|ui| {
+                    ui.label("Paths:");
+                    ui.label("(stdin)").on_hover_text("Searching the input piped in at startup");
+                });
+            } else {
+                let mut removed_path = None;
+                for (i, path) in self.paths.iter_mut().enumerate() {
+                    let checked = self.path_checks.get(i).and_then(|(_, kind)| *kind);
+                    ui.horizontal(|ui| {
+                        ui.label(if i == 0 { "Paths:" } else { "" });
+                        let (icon, kind) = match checked {
+                            Some(true) => ("📁", "Directory"),
+                            Some(false) => ("📄", "File"),
+                            None => ("❓", "Not found"),
+                        };
+                        ui.label(icon).on_hover_text(kind);
+                        let missing = checked.is_none() && !path.trim().is_empty();
+                        ui.scope(|ui| {
+                            if missing {
+                                let stroke = egui::Stroke::new(1.0, egui::Color32::RED);
+                                let visuals = ui.visuals_mut();
+                                visuals.widgets.inactive.bg_stroke = stroke;
+                                visuals.widgets.hovered.bg_stroke = stroke;
+                                visuals.selection.stroke = stroke;
+                            }
+                            ui.text_edit_singleline(path);
+                        });
+                        if missing {
+                            ui.colored_label(egui::Color32::RED, "Path not found");
+                        }
+                        if ui.small_button("✖").on_hover_text("Remove this path").clicked() {
+                            removed_path = Some(i);
+                        }
+                    });
+                    if checked.is_some()
+                        && let Some(ancestor) = path_breadcrumb(ui, path)
+                    {
+                        *path = ancestor;
+                    }
+                }
+                if let Some(i) = removed_path {
+                    self.paths.remove(i);
+                }
+                ui.horizontal(|ui| {
+                    if self.paths.is_empty() {
+                        ui.label("Paths:");
+                    }
+                    if ui.button("Add path").clicked() {
+                        self.paths.push(String::new());
+                    }
+                    if ui.button("Browse...").clicked()
+                        && let Some(path) = rfd::FileDialog::new().pick_folder()
+                    {
+                        self.paths.push(path.display().to_string());
+                    }
+                    if ui.button("Pick file...").clicked()
+                        && let Some(path) = rfd::FileDialog::new().pick_file()
+                    {
+                        self.paths.push(path.display().to_string());
+                    }
+                    let mut picked_path = None;
+                    ui.add_enabled_ui(!settings.recent_paths.is_empty(), |ui| {
+                        egui::ComboBox::from_id_source(("recent_paths", self.serial))
+                            .selected_text("Recent")
+                            .show_ui(ui, |ui| {
+                                for p in &settings.recent_paths {
+                                    if ui.selectable_label(false, p).clicked() {
+                                        picked_path = Some(p.clone());
+                                    }
+                                }
+                            });
+                    })
+                    .response
+                    .on_hover_text("Search a recently used path instead of the ones above");
+                    if let Some(path) = picked_path {
+                        self.paths = vec![path];
+                    }
+                });
+            }
 
             
-            ui.collapsing("Options", |ui| {
-                 ui.checkbox(&mut self.case_insensitive, "Case Insensitive (-i)");
+            egui::CollapsingHeader::new("Options").id_source(("options", self.serial)).show(ui, |ui| {
+                 ui.horizontal(|ui| {
+                    ui.label("Case:");
+                    ui.radio_value(&mut self.case_mode, CaseMode::Sensitive, "Sensitive (-s)");
+                    ui.radio_value(&mut self.case_mode, CaseMode::Insensitive, "Insensitive (-i)");
+                    ui.radio_value(&mut self.case_mode, CaseMode::Smart, "Smart (-S)");
+                 });
                  ui.checkbox(&mut self.search_hidden, "Search Hidden Files (--hidden)");
                  ui.checkbox(&mut self.follow_symlinks, "Follow Symlinks (-L)");
                  ui.horizontal(|ui| {
-                    ui.label("Globs (-g):");
-                    
-                    let _response = ui.add(egui::TextEdit::singleline(&mut self.globs).hint_text("e.g., !*.log"));
+                    ui.label("Ignore files:");
+                    ui.radio_value(&mut self.ignore_level, IgnoreLevel::Respect, "Respect .gitignore");
+                    ui.radio_value(&mut self.ignore_level, IgnoreLevel::NoIgnore, "No ignore (--no-ignore)");
+                    ui.radio_value(&mut self.ignore_level, IgnoreLevel::Unrestricted, "Unrestricted (-uu)");
+                 });
+                 ui.label("Extra ignore files (--ignore-file):");
+                 string_list_editor(ui, &mut self.ignore_files, "path to an ignore file", "Add ignore file");
+                 if ui.button("Pick ignore file...").clicked()
+                     && let Some(path) = rfd::FileDialog::new().pick_file()
+                 {
+                     self.ignore_files.push(path.display().to_string());
+                 }
+                 ui.horizontal(|ui| {
+                    ui.label("Binary files:");
+                    ui.radio_value(&mut self.binary_mode, BinaryMode::Skip, "Skip");
+                    ui.radio_value(&mut self.binary_mode, BinaryMode::Text, "As text (-a)");
+                    ui.radio_value(&mut self.binary_mode, BinaryMode::Binary, "Report matches (--binary)");
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Encoding (-E):");
+                    egui::ComboBox::from_id_source(("encoding", self.serial))
+                        .selected_text(self.encoding.as_deref().unwrap_or("auto"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.encoding, None, "auto");
+                            for &encoding in COMMON_ENCODINGS {
+                                ui.selectable_value(&mut self.encoding, Some(encoding.to_string()), encoding);
+                            }
+                        });
+                 });
+                 ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.limit_depth, "Limit depth (--max-depth)");
+                    ui.add_enabled(self.limit_depth, egui::DragValue::new(&mut self.max_depth).clamp_range(0..=100));
+                 });
+                 ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.limit_line_range, "Only lines")
+                        .on_hover_text("Filtered by this app, not rg: rg still searches whole files and matches outside the range are dropped.");
+                    ui.add_enabled(self.limit_line_range, egui::DragValue::new(&mut self.min_line).clamp_range(1..=self.max_line));
+                    ui.label("to");
+                    ui.add_enabled(self.limit_line_range, egui::DragValue::new(&mut self.max_line).clamp_range(self.min_line..=u64::MAX));
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Max file size (--max-filesize):");
+                    ui.add(egui::TextEdit::singleline(&mut self.max_filesize).hint_text("e.g. 10M").desired_width(80.0))
+                        .on_hover_text("Skip files larger than this. Bytes, or with a K, M or G suffix.");
+                    let size = self.max_filesize.trim();
+                    if !size.is_empty() && !is_valid_filesize(size) {
+                        ui.colored_label(egui::Color32::RED, "Use a number with an optional K, M or G suffix");
+                    }
+                 });
+                 ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.limit_threads, "Limit threads (-j)")
+                        .on_hover_text("Cap rg's CPU use, e.g. on battery. 1 searches single-threaded.");
+                    ui.add_enabled(self.limit_threads, egui::DragValue::new(&mut self.threads).clamp_range(1..=256));
+                 });
+                 if ui.checkbox(&mut self.fixed_string, "Fixed String (-F)").changed() {
+                     self.validate_query();
+                 }
+                 ui.checkbox(&mut self.whole_word, "Whole Word (-w)");
+                 ui.horizontal(|ui| {
+                    ui.add_enabled(!self.whole_file_pattern, egui::Checkbox::new(&mut self.multiline, "Multiline (-U)"));
+                    ui.add_enabled(self.multiline && !self.whole_file_pattern, egui::Checkbox::new(&mut self.multiline_dotall, "Dot matches newline (--multiline-dotall)"));
+                 });
+                 ui.checkbox(&mut self.whole_file_pattern, "Whole-file pattern (-U --multiline-dotall)")
+                     .on_hover_text("Lets . match newlines so one pattern can span a whole file. ^ and $ still match at every line; anchor to the start and end of the file with \\A and \\z. Long matches are cut to the max line length.");
+                 let pcre2_missing = matches!(&settings.rg_info, Some(Ok(info)) if info.pcre2 == Some(false));
+                 if ui.add_enabled(!pcre2_missing || self.pcre2, egui::Checkbox::new(&mut self.pcre2, "PCRE2 (-P)"))
+                     .on_hover_text("Enables lookaround and backreferences, e.g. foo(?=bar). Requires rg built with PCRE2.")
+                     .on_disabled_hover_text("This rg was built without PCRE2.")
+                     .changed()
+                 {
+                     self.validate_query();
+                 }
+                 ui.checkbox(&mut self.invert_match, "Invert Match (-v)");
+                 ui.checkbox(&mut self.only_matching, "Only matching (-o)")
+                     .on_hover_text("Show just the matched text, one row per match, plus a frequency list.");
+                 ui.checkbox(&mut self.count_mode, "Count only (--count-matches)").on_hover_text("Show one row per file with its number of matches.");
+                 ui.checkbox(&mut self.filename_mode, "File names only (--files)")
+                     .on_hover_text("Match the query against the paths of the files rg would search, instead of their contents. Globs, types and ignore settings still apply.");
+                 ui.horizontal(|ui| {
+                    ui.label("Context before (-B):");
+                    ui.add(egui::DragValue::new(&mut self.context_before).clamp_range(0..=50));
+                    ui.label("after (-A):");
+                    ui.add(egui::DragValue::new(&mut self.context_after).clamp_range(0..=50));
+                 });
+                 ui.horizontal(|ui| {
+                    let mut limited = self.max_count_per_file.is_some();
+                    if ui.checkbox(&mut limited, "Max matches per file (--max-count):")
+                        .on_hover_text("Stop reading each file after this many matching lines. 1 is a fast way to find which files contain the pattern.")
+                        .changed()
+                    {
+                        self.max_count_per_file = limited.then_some(1);
+                    }
+                    if let Some(max_count) = &mut self.max_count_per_file {
+                        ui.add(egui::DragValue::new(max_count).clamp_range(1..=10_000));
+                    }
+                 });
+                 ui.horizontal(|ui| {
+                    let mut limited = self.time_limit_secs.is_some();
+                    if ui.checkbox(&mut limited, "Time limit (s):").changed() {
+                        self.time_limit_secs = limited.then_some(120);
+                    }
+                    if let Some(limit) = &mut self.time_limit_secs {
+                        ui.add(egui::DragValue::new(limit).clamp_range(1..=3600));
+                    }
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Max results:");
+                    ui.add(egui::DragValue::new(&mut self.max_results).speed(100))
+                        .on_hover_text("Stop the search after this many matches. 0 means unlimited.");
+                    ui.label("Max line length:");
+                    ui.add(egui::DragValue::new(&mut self.max_line_chars).speed(10))
+                        .on_hover_text("Longer lines (e.g. minified files) are cut around the match. 0 means unlimited.");
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Result font:");
+                    ui.selectable_value(&mut self.result_font, ResultFont::Monospace, "Monospace");
+                    ui.selectable_value(&mut self.result_font, ResultFont::Proportional, "Proportional");
+                    ui.add(egui::DragValue::new(&mut self.result_font_size).clamp_range(6.0..=48.0).speed(0.5).suffix(" pt"));
+                    if ui.add_enabled(self.result_font_size != DEFAULT_RESULT_FONT_SIZE, egui::Button::new("Reset")).clicked() {
+                        self.result_font_size = DEFAULT_RESULT_FONT_SIZE;
+                    }
+                 });
+                 ui.label("Include globs (-g):");
+                 string_list_editor(ui, &mut self.include_globs, "e.g., *.rs", "Add include glob");
+                 ui.label("Exclude globs (-g !):");
+                 string_list_editor(ui, &mut self.exclude_globs, "e.g., *.log", "Add exclude glob");
+                 ui.add_enabled(
+                     settings.rg_supports(MIN_RG_GLOB_CASE_INSENSITIVE) || self.glob_case_insensitive,
+                     egui::Checkbox::new(&mut self.glob_case_insensitive, "Case-insensitive globs (--glob-case-insensitive)"),
+                 )
+                 .on_disabled_hover_text(format!("Needs ripgrep {} or newer.", format_version(MIN_RG_GLOB_CASE_INSENSITIVE)));
+                 ui.horizontal(|ui| {
+                    type_filter_menu(ui, "Types (--type)", "all", &mut self.types);
+                    type_filter_menu(ui, "Exclude types (--type-not)", "none", &mut self.types_not);
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Extra args:");
+                    ui.add(egui::TextEdit::singleline(&mut self.extra_args).hint_text("e.g., --max-filesize 1M -g '!*.min.js'"))
+                        .on_hover_text("Passed to rg as-is after the other options; quotes group words. rg reports invalid flags as an error.");
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("rg path:");
+                    if ui.text_edit_singleline(&mut settings.rg_path).changed() {
+                        settings.rg_info = None;
+                    }
+                    if ui.button("Test").clicked() {
+                        settings.rg_info = Some(detect_rg(&settings.rg_path));
+                    }
+                    let too_old = settings.rg_too_old();
+                    match &settings.rg_info {
+                        Some(Ok(info)) if too_old.is_some() => { ui.colored_label(egui::Color32::YELLOW, &info.version_line); }
+                        Some(Ok(info)) => { ui.colored_label(egui::Color32::GREEN, &info.version_line); }
+                        Some(Err(e)) => { ui.colored_label(egui::Color32::RED, e); }
+                        None => {}
+                    }
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Editor command:");
+                    ui.add(egui::TextEdit::singleline(&mut settings.editor_command).hint_text("e.g., code -g {path}:{line}:{col}"))
+                        .on_hover_text("Placeholders: {path}, {line}, {col} and {offset} (bytes from the start of the file, counting from 0; vim's :goto counts from 1). Quote a program path with spaces, e.g. \"C:\\Program Files\\...\". Leave empty to use $EDITOR or the system default application.");
+                 });
+                 ui.horizontal(|ui| {
+                    ui.label("Custom command:");
+                    ui.add(egui::TextEdit::singleline(&mut settings.custom_command).hint_text("e.g., wezterm start --cwd {dir}"))
+                        .on_hover_text("Run from the ▶ button on each result. Placeholders: {path}, {line}, {dir}.");
                  });
             });
             ui.separator();
 
 
             
+            if let Some(message) = settings.rg_too_old() {
+                ui.colored_label(egui::Color32::YELLOW, message);
+            }
             ui.horizontal(|ui|{
-                if ui.button("Search").clicked() && self.search_result_receiver.is_none() {
-                    self.results.clear();
-                    self.error_message = None;
-                    self.search_status = "Starting search...".to_string();
-
-                    let (tx, rx) = unbounded::<SearchResult>();
-                    self.search_result_receiver = Some(rx);
-
-                    let query = self.query.clone();
-                    let path = self.path.clone();
-                    let options = crate::ripgrep::ripgrep::RgOptions {
-                        case_insensitive: self.case_insensitive,
-                        search_hidden: self.search_hidden,
-                        follow_symlinks: self.follow_symlinks,
-                        globs: if self.globs.is_empty() { None } else { Some(self.globs.clone()) },
-                    };
-
-                    
-                    thread::spawn(move || {
-                        run_ripgrep(query, path, options, tx);
-                    });
+                // An empty pattern matches every line, which is almost never what was meant.
+                let query_empty = self.query.trim().is_empty();
+                let blocked = self.missing_path().map(|path| format!("Path not found: {}", path)).or_else(|| settings.rg_too_old());
+                if ui.add_enabled(self.query_error.is_none() && !query_empty && blocked.is_none(), egui::Button::new("Search"))
+                    .on_disabled_hover_text(blocked.as_deref().unwrap_or("Enter a valid query to search."))
+                    .clicked()
+                    && self.search_result_receiver.is_none()
+                {
+                    self.request_search(settings);
+                }
+                if ui.add_enabled(self.last_search.is_some() && self.search_result_receiver.is_none(), egui::Button::new("Rerun"))
+                    .on_hover_text("Repeat the last search with the query, paths and options it used")
+                    .clicked()
+                {
+                    self.rerun_search();
                 }
-                 ui.label(&self.search_status);
+                if ui.add_enabled(self.search_result_receiver.is_some(), egui::Button::new("Cancel")).clicked() {
+                    self.cancel_search();
+                    self.search_status = "Search cancelled".to_string();
+                }
+                if ui.add_enabled(self.search_result_receiver.is_none(), egui::Button::new("Clear"))
+                    .on_hover_text("Remove the results, errors and result filter")
+                    .clicked()
+                {
+                    self.clear_results();
+                }
+                if ui.button("Show command").on_hover_text("Show the rg command this search would run").clicked() {
+                    self.show_command(settings);
+                }
+                ui.checkbox(&mut self.live_search, "Live search");
+                ui.checkbox(&mut self.rerun_on_focus, "Rerun on focus")
+                    .on_hover_text("Repeat the last search when this window regains focus, e.g. after editing files elsewhere.");
+                 if self.search_result_receiver.is_some() {
+                     ui.add(egui::Spinner::new());
+                 }
+                 if query_empty && self.search_result_receiver.is_none() {
+                     ui.weak("Type a query to search.");
+                 } else {
+                     ui.label(&self.search_status);
+                 }
+                 if self.search_result_receiver.is_some() && self.files_scanned > 0 {
+                     ui.label(format!("Scanned {} matching files", group_digits(self.files_scanned)));
+                 }
+                 if let Some(elapsed) = self.search_started.map(|started| started.elapsed()).or(self.search_elapsed) {
+                     ui.label(format!("Elapsed: {:.1}s", elapsed.as_secs_f32()));
+                 }
             });
+            if self.search_result_receiver.is_none()
+                && let Some(stats) = &self.search_stats
+            {
+                ui.label(format!(
+                    "Searched {} bytes in {} files, {} matches on {} lines in {}",
+                    stats.bytes_searched, stats.files_searched, stats.matches, stats.matched_lines, stats.elapsed
+                ));
+            }
 
 
             
             if let Some(err) = &self.error_message {
                 ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
             }
+            if let Some(rg_path) = &self.rg_not_found {
+                rg_install_hint(ui, rg_path);
+            }
+            if !self.warnings.is_empty() {
+                let mut dismissed = false;
+                ui.horizontal(|ui| {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {} warnings from rg", self.warnings.len()));
+                    dismissed = ui.small_button("Dismiss").clicked();
+                });
+                egui::CollapsingHeader::new("Show warnings").id_source(("rg_warnings", self.serial)).show(ui, |ui| {
+                    egui::ScrollArea::vertical().id_source(("rg_warnings_scroll", self.serial)).max_height(120.0).show(ui, |ui| {
+                        for warning in &self.warnings {
+                            ui.label(egui::RichText::new(warning).monospace());
+                        }
+                    });
+                });
+                if dismissed {
+                    self.warnings.clear();
+                }
+            }
             ui.separator();
 
             
-            ui.heading("Results");
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                if self.results.is_empty() && self.error_message.is_none() && self.search_result_receiver.is_none() {
-                     ui.label("No results yet. Enter a query and path, then click Search.");
-                } else {
-                    for m in &self.results { 
-                        ui.group(|ui| {
-                             ui.strong(format!("{}:{}", m.path, m.line_number)); 
-                             ui.monospace(&m.line_text); 
+            ui.horizontal(|ui| {
+                ui.heading("Results");
+                egui::Frame::none()
+                    .fill(ui.visuals().selection.bg_fill)
+                    .rounding(8.0)
+                    .inner_margin(egui::Margin::symmetric(6.0, 1.0))
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(self.results.len().to_string()).small().color(ui.visuals().selection.stroke.color));
+                    });
+                egui::ComboBox::from_id_source(("result_sort", self.serial))
+                    .selected_text(self.result_sort.label())
+                    .show_ui(ui, |ui| {
+                        for sort in [ResultSort::FileOrder, ResultSort::Path, ResultSort::LineNumber, ResultSort::Modified] {
+                            ui.selectable_value(&mut self.result_sort, sort, sort.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Path A–Z and Newest first make rg sort (--sort path / --sortr modified), which disables its parallelism and can be much slower on large trees. Newest first applies from the next search.");
+                ui.checkbox(&mut self.auto_scroll, "Auto-scroll")
+                    .on_hover_text("Follow new results as they arrive. Turn off to read earlier results undisturbed.");
+                ui.checkbox(&mut self.group_by_file, "Group by file");
+                if self.group_by_file {
+                    ui.checkbox(&mut self.show_timings, "Show timings")
+                        .on_hover_text("Show how long rg spent on each file, to find the ones slowing a search down. Applies from the next search.");
+                }
+                ui.checkbox(&mut self.relative_paths, "Relative paths")
+                    .on_hover_text("Show paths relative to the searched path they were found under");
+                ui.selectable_value(&mut self.row_density, RowDensity::Comfortable, "Comfortable");
+                ui.selectable_value(&mut self.row_density, RowDensity::Compact, "Compact");
+                ui.add_enabled(self.row_density == RowDensity::Comfortable, egui::Checkbox::new(&mut self.wrap_lines, "Wrap lines"))
+                    .on_hover_text("Wrap long result lines instead of clipping them (comfortable rows only)");
+                ui.checkbox(&mut self.show_offsets, "Byte offsets")
+                    .on_hover_text("Show where each match starts, in bytes from the start of its file");
+                ui.checkbox(&mut self.syntax_highlighting, "Syntax colors")
+                    .on_hover_text(format!("Color result lines by language for .{} files", SYNTAX_EXTENSIONS.join(", .")));
+                if self.group_by_file {
+                    if ui.button("Collapse all").clicked() {
+                        self.collapsed_files = self.results.iter().map(|m| m.path.clone()).collect();
+                    }
+                    if ui.button("Expand all").clicked() {
+                        self.collapsed_files.clear();
+                    }
+                }
+                if ui.add_enabled(!self.display_order.is_empty(), egui::Button::new("Previous match"))
+                    .on_hover_text("Select the previous result (Shift+F3)")
+                    .clicked()
+                {
+                    self.step_match(false);
+                }
+                if ui.add_enabled(!self.display_order.is_empty(), egui::Button::new("Next match"))
+                    .on_hover_text("Select the next result (F3)")
+                    .clicked()
+                {
+                    self.step_match(true);
+                }
+                if ui.add_enabled(!self.results.is_empty(), egui::Button::new("Open all"))
+                    .on_hover_text("Open every matching file in the editor, each at its first match")
+                    .clicked()
+                {
+                    self.request_open_files(PendingAction::OpenAll, settings);
+                }
+                if ui.add_enabled(!self.display_order.is_empty(), egui::Button::new("Copy all"))
+                    .on_hover_text("Copy the shown results as path:line:text lines")
+                    .clicked()
+                {
+                    ui.ctx().copy_text(to_plain_text(self.display_order.iter().map(|&i| &self.results[i])));
+                }
+                let selected = self.selected_results();
+                if selected.len() > 1 {
+                    ui.label(format!("{} selected:", selected.len()));
+                    if ui.button("Copy").on_hover_text("Copy the selected results as path:line:text lines").clicked() {
+                        ui.ctx().copy_text(to_plain_text(selected.iter().map(|&i| &self.results[i])));
+                    }
+                    if ui.button("Open").on_hover_text("Open each selected file in the editor, at its first selected match").clicked() {
+                        self.request_open_files(PendingAction::OpenSelected, settings);
+                    }
+                }
+                if ui.add_enabled(!self.results.is_empty(), egui::Button::new("Export..."))
+                    .on_hover_text("Save results as .json, .csv or .txt (path:line:text)")
+                    .clicked()
+                    && let Some(file) = rfd::FileDialog::new()
+                        .set_file_name("results.txt")
+                        .add_filter("Text", &["txt"])
+                        .add_filter("JSON", &["json"])
+                        .add_filter("CSV", &["csv"])
+                        .save_file()
+                    && let Err(e) = export_results(&file, &self.results)
+                {
+                    self.error_message = Some(e);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Filter results:");
+                if ui.add(egui::TextEdit::singleline(&mut self.result_filter).hint_text("narrow by path or line text"))
+                    .changed()
+                {
+                    self.refresh_display_order();
+                }
+                if !self.result_filter.trim().is_empty() {
+                    ui.label(format!("{} of {} results shown", self.display_order.len(), self.results.len()));
+                }
+            });
+            if !self.results.is_empty() {
+                self.refresh_extension_counts();
+                let mut counts: Vec<(&String, &u64)> = self.extension_counts.iter().collect();
+                counts.sort_unstable_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new("By extension:").small().weak());
+                    let summary = counts.iter().map(|(extension, n)| format!("{}: {}", extension, n)).collect::<Vec<_>>().join(", ");
+                    ui.label(egui::RichText::new(summary).small().monospace());
+                });
+            }
+            if self.only_matching && !self.results.is_empty() {
+                self.refresh_match_frequencies();
+                let mut picked = None;
+                egui::CollapsingHeader::new(format!("Match frequencies ({} distinct)", self.match_frequencies.len()))
+                    .id_source(("match_frequencies", self.serial))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_source(("match_frequencies_scroll", self.serial)).max_height(150.0).show(ui, |ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                for (text, count) in &self.match_frequencies {
+                                    if ui.selectable_label(false, egui::RichText::new(format!("{} ×{}", text, count)).monospace())
+                                        .on_hover_text("Filter results to this match")
+                                        .clicked()
+                                    {
+                                        picked = Some(text.clone());
+                                    }
+                                }
+                            });
+                        });
+                    });
+                if let Some(text) = picked {
+                    self.result_filter = text;
+                    self.refresh_display_order();
+                }
+            }
+            if self.results.is_empty() && self.error_message.is_none() && self.rg_not_found.is_none() && self.search_result_receiver.is_none() {
+                 ui.label("No results yet. Enter a query and path, then click Search.");
+            } else {
+                // Row heights, wrapping and highlight layouts all resolve `TextStyle::Monospace`,
+                // so overriding it here applies the font to every part of a result row.
+                let result_font = egui::FontId::new(self.result_font_size, self.result_font.family());
+                ui.style_mut().text_styles.insert(egui::TextStyle::Monospace, result_font);
+                let row_height = self.result_row_height(ui);
+                let mut action = None;
+                // stick_to_bottom only pins while already at the bottom, so switching it off
+                // leaves the current offset alone.
+                let mut scroll_area = egui::ScrollArea::vertical()
+                    .id_source(("results", self.serial))
+                    .auto_shrink(false)
+                    .stick_to_bottom(self.auto_scroll);
+                if let Some(offset) = self.pending_scroll_restore {
+                    let (_, viewport_height) = self.results_viewport;
+                    if self.auto_scroll {
+                        // Following new results wins over going back.
+                        self.pending_scroll_restore = None;
+                    } else if self.search_result_receiver.is_none() || self.results_content_height >= offset + viewport_height {
+                        scroll_area = scroll_area.vertical_scroll_offset(offset);
+                        self.pending_scroll_restore = None;
+                    }
+                }
+                let scroll_to_selected = std::mem::take(&mut self.scroll_to_selected);
+                if scroll_to_selected
+                    && !self.group_by_file
+                    && !self.wraps_rows()
+                    && let Some(row) = self.selected_row()
+                {
+                    let (offset, viewport_height) = self.results_viewport;
+                    let row_top = row as f32 * (row_height + ui.spacing().item_spacing.y);
+                    if row_top < offset {
+                        scroll_area = scroll_area.vertical_scroll_offset(row_top);
+                    } else if row_top + row_height > offset + viewport_height {
+                        scroll_area = scroll_area.vertical_scroll_offset(row_top + row_height - viewport_height);
+                    }
+                }
+                if scroll_to_selected
+                    && self.group_by_file
+                    && let Some(index) = self.selected_index
+                {
+                    // A collapsed group is opened when navigation lands inside it.
+                    self.collapsed_files.remove(&self.results[index].path);
+                }
+                let output = if self.group_by_file || self.wraps_rows() {
+                    // Like `show_rows`, but with file headers and each row's own height.
+                    scroll_area.show_viewport(ui, |ui, viewport| {
+                        if self.wraps_rows() {
+                            let margin = egui::Frame::group(ui.style()).inner_margin.sum().x;
+                            self.update_wrapped_heights(ui, ui.available_width() - margin);
+                        }
+                        let items = self.result_list_items();
+                        let spacing = ui.spacing().item_spacing.y;
+                        let header_height = ui.spacing().interact_size.y;
+                        // The top of each item, then the bottom of the list.
+                        let mut tops = Vec::with_capacity(items.len() + 1);
+                        let mut top = 0.0;
+                        for item in &items {
+                            tops.push(top);
+                            top += spacing + match *item {
+                                ListItem::Header { .. } => header_height,
+                                ListItem::Row(index) if self.wraps_rows() => self.wrapped_heights[index],
+                                ListItem::Row(_) => row_height,
+                            };
+                        }
+                        tops.push(top);
+                        ui.set_height((top - spacing).max(0.0));
+                        let y = |top: f32| ui.max_rect().top() + top;
+                        if scroll_to_selected
+                            && let Some(selected) = self.selected_index
+                            && let Some(at) = items.iter().position(|item| *item == ListItem::Row(selected))
+                        {
+                            ui.scroll_to_rect(egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), y(tops[at])..=y(tops[at + 1] - spacing)), None);
+                        }
+                        let first = tops.partition_point(|&top| top <= viewport.min.y).saturating_sub(1);
+                        let last = tops.partition_point(|&top| top < viewport.max.y).min(items.len());
+                        let rect = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), y(tops[first])..=y(tops[last]));
+                        let mut toggled = None;
+                        ui.allocate_ui_at_rect(rect, |ui| {
+                            ui.skip_ahead_auto_ids(first);
+                            for item in &items[first..last] {
+                                match *item {
+                                    ListItem::Header { start, end } => {
+                                        if self.show_group_header(ui, start, end, header_height) {
+                                            toggled = Some(self.results[self.display_order[start]].path.clone());
+                                        }
+                                    }
+                                    ListItem::Row(index) => {
+                                        let row_height = if self.wraps_rows() { self.wrapped_heights[index] } else { row_height };
+                                        self.show_result_row(ui, settings, index, row_height, &mut action);
+                                    }
+                                }
+                            }
                         });
+                        if let Some(path) = toggled
+                            && !self.collapsed_files.remove(&path)
+                        {
+                            self.collapsed_files.insert(path);
+                        }
+                    })
+                } else {
+                    scroll_area.show_rows(ui, row_height, self.display_order.len(), |ui, row_range| {
+                        for row in row_range {
+                            self.show_result_row(ui, settings, self.display_order[row], row_height, &mut action);
+                        }
+                    })
+                };
+                self.results_viewport = (output.state.offset.y, output.inner_rect.height());
+                self.results_content_height = output.content_size.y;
+                match action {
+                    Some(RowAction::Select(index)) => {
+                        let modifiers = ui.input(|i| i.modifiers);
+                        self.click_row(index, modifiers);
+                    }
+                    Some(RowAction::Open(index)) => {
+                        self.select_only(index);
+                        self.open_result(index, settings);
+                    }
+                    Some(RowAction::Reveal(index)) => {
+                        if let Err(e) = reveal_in_file_manager(&self.results[index].path) {
+                            self.error_message = Some(e);
+                        }
+                    }
+                    Some(RowAction::RunCommand(index)) => {
+                        let m = &self.results[index];
+                        if let Err(e) = run_command_template(&settings.custom_command, &m.path, m.line_number) {
+                            self.error_message = Some(e);
+                        }
+                    }
+                    None => {}
+                }
+            }
+        });
+    }
+}
+
+/// The app window: a tab bar over independent searches, which keep running in the background
+/// while another tab is shown.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MyApp {
+    tabs: Vec<SearchTab>,
+    active_tab: usize,
+    settings: Settings,
+}
+
+impl MyApp {
+    pub fn new(cc: &eframe::CreationContext<'_>, launch: LaunchArgs) -> Self {
+        let mut app: Self = cc.storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        if app.tabs.is_empty() {
+            app.tabs.push(SearchTab::default());
+        }
+        app.settings.rg_info = Some(detect_rg(&app.settings.rg_path));
+        app.active_tab = app.active_tab.min(app.tabs.len() - 1);
+        // Without a saved choice eframe follows the OS theme.
+        if let Some(dark_mode) = app.settings.dark_mode {
+            cc.egui_ctx.set_visuals(if dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() });
+        }
+        app.tabs[app.active_tab].apply_launch_args(launch, &mut app.settings);
+        app
+    }
+
+    /// Opens a tab with the active tab's saved options and paths but no query or results.
+    fn new_tab(&mut self) {
+        let tab = serde_json::to_value(&self.tabs[self.active_tab])
+            .and_then(serde_json::from_value)
+            .unwrap_or_default();
+        self.tabs.push(tab);
+        self.active_tab = self.tabs.len() - 1;
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        let mut tab = self.tabs.remove(index);
+        tab.cancel_search();
+        if self.active_tab > index || self.active_tab == self.tabs.len() {
+            self.active_tab -= 1;
+        }
+    }
+
+    fn show_tab_bar(&mut self, ctx: &egui::Context) {
+        let mut closed = None;
+        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (i, tab) in self.tabs.iter().enumerate() {
+                    let query = tab.query.trim();
+                    let mut title = if query.is_empty() { format!("Search {}", i + 1) } else { query.chars().take(24).collect() };
+                    if tab.search_result_receiver.is_some() {
+                        title.push_str(" …");
+                    }
+                    if ui.selectable_label(i == self.active_tab, title).clicked() {
+                        self.active_tab = i;
                     }
+                    if self.tabs.len() > 1 && ui.small_button("✖").on_hover_text("Close this tab").clicked() {
+                        closed = Some(i);
+                    }
+                    ui.separator();
+                }
+                if ui.button("+").on_hover_text("New tab with this tab's settings and paths").clicked() {
+                    self.new_tab();
                 }
             });
         });
+        if let Some(index) = closed {
+            self.close_tab(index);
+        }
+    }
+}
 
-        if self.search_result_receiver.is_some() {
-             ctx.request_repaint();
+impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        for tab in &mut self.tabs {
+            tab.poll(ctx, &mut self.settings);
+        }
+        self.show_tab_bar(ctx);
+        self.settings.show_about_window(ctx);
+        self.tabs[self.active_tab].show(ctx, &mut self.settings);
+    }
+}
+
+/// Returns a warning when `path` is a filesystem root, or the home directory searched without
+/// any ignore rules to prune it, since those searches can run practically forever.
+fn huge_root_warning(path: &str, ignore_level: IgnoreLevel) -> Option<String> {
+    let path = std::path::Path::new(path.trim());
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    if path.parent().is_none() {
+        return Some(format!("{} is a filesystem root. Searching it can take a very long time.", path.display()));
+    }
+    let home = UserDirs::new().map(|ud| ud.home_dir().to_path_buf())?;
+    if path != home {
+        return None;
+    }
+    let has_ignore_files = [".ignore", ".rgignore", ".gitignore"].iter().any(|f| home.join(f).exists());
+    if ignore_level != IgnoreLevel::Respect || !has_ignore_files {
+        return Some(format!("{} is your home directory and no ignore rules will prune it. Searching it can take a very long time.", path.display()));
+    }
+    None
+}
+
+/// A result's `path:line` label that opens the result when clicked. Line text stays
+/// selectable for copying, so the location is the dependable click target.
+fn location_link(ui: &mut egui::Ui, text: impl Into<egui::WidgetText>) -> bool {
+    ui.add(egui::Label::new(text).selectable(false).sense(egui::Sense::click()))
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text("Click to open")
+        .clicked()
+}
+
+/// Shows `path` as a row of its ancestors, outermost first, each clickable to search from
+/// there instead. Returns the clicked ancestor.
+fn path_breadcrumb(ui: &mut egui::Ui, path: &str) -> Option<String> {
+    // Relative paths are resolved against the working directory, which is what rg searches.
+    let path = std::path::absolute(path.trim()).ok()?;
+    let ancestors: Vec<&Path> = path.ancestors().collect();
+    let mut picked = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        ui.add_space(ui.spacing().indent);
+        for (i, ancestor) in ancestors.iter().rev().enumerate() {
+            let name = ancestor.file_name().map_or_else(|| ancestor.display().to_string(), |n| n.to_string_lossy().into_owned());
+            if i + 1 == ancestors.len() {
+                ui.label(egui::RichText::new(name).small());
+            } else {
+                if ui.link(egui::RichText::new(name).small()).on_hover_text("Search from here").clicked() {
+                    picked = Some(ancestor.display().to_string());
+                }
+                ui.label(egui::RichText::new("›").small().weak());
+            }
+        }
+    });
+    picked
+}
+
+/// A filename-mode row's path with the matched ranges highlighted. `path` is the displayed
+/// form of `m.path`, which may have a search root stripped from the front.
+fn name_match_layout(ui: &egui::Ui, m: &GuiMatch, path: &str) -> LayoutJob {
+    let shift = m.path.len() - path.len();
+    let ranges: Vec<(usize, usize)> = m
+        .submatches
+        .iter()
+        .filter(|&&(start, _)| start >= shift)
+        .map(|&(start, end)| (start - shift, end - shift))
+        .collect();
+    highlighted_line(ui, path, &ranges)
+}
+
+/// Whether `next` is a later, non-adjacent block of lines from the same file as `prev`,
+/// counting the context lines shown with each.
+fn starts_new_block(prev: &GuiMatch, next: &GuiMatch) -> bool {
+    let is_line = |m: &GuiMatch| m.match_count.is_none() && m.binary_offset.is_none() && !m.name_match;
+    if prev.path != next.path || !is_line(prev) || !is_line(next) {
+        return false;
+    }
+    let prev_end = prev.context_after.last().map_or(0, |c| c.line_number).max(prev.line_number + prev.line_text.lines().count().saturating_sub(1) as u64);
+    let next_start = next.context_before.first().map_or(next.line_number, |c| c.line_number);
+    next_start > prev_end + 1
+}
+
+/// Formats `n` with comma thousands separators, e.g. `1,234`.
+fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Prominent panel explaining how to install ripgrep when `rg_path` could not be spawned.
+fn rg_install_hint(ui: &mut egui::Ui, rg_path: &str) {
+    egui::Frame::group(ui.style())
+        .fill(ui.visuals().extreme_bg_color)
+        .show(ui, |ui| {
+            ui.heading("ripgrep not found");
+            ui.label(format!(
+                "'{}' could not be started. Install ripgrep and make sure it is on your PATH, or set the rg path in Options.",
+                rg_path
+            ));
+            let install = if cfg!(target_os = "macos") {
+                "brew install ripgrep"
+            } else if cfg!(target_os = "windows") {
+                "scoop install ripgrep   (or: winget install BurntSushi.ripgrep.MSVC)"
+            } else {
+                "sudo apt install ripgrep   (or your distribution's package manager)"
+            };
+            ui.horizontal(|ui| {
+                ui.label("Install with:");
+                ui.code(install);
+            });
+            ui.hyperlink_to("Download a release from GitHub", "https://github.com/BurntSushi/ripgrep/releases");
+        });
+}
+
+/// One removable text field per entry, plus a button that appends an empty entry.
+fn string_list_editor(ui: &mut egui::Ui, items: &mut Vec<String>, hint: &str, add_label: &str) {
+    let mut removed = None;
+    for (i, item) in items.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(item).hint_text(hint));
+            if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                removed = Some(i);
+            }
+        });
+    }
+    if let Some(i) = removed {
+        items.remove(i);
+    }
+    if ui.button(add_label).clicked() {
+        items.push(String::new());
+    }
+}
+
+/// Menu of checkboxes over `COMMON_TYPES`; the button label lists the current selection.
+fn type_filter_menu(ui: &mut egui::Ui, label: &str, empty_text: &str, selected: &mut Vec<String>) {
+    let title = if selected.is_empty() {
+        format!("{}: {}", label, empty_text)
+    } else {
+        format!("{}: {}", label, selected.join(", "))
+    };
+    ui.menu_button(title, |ui| {
+        for &file_type in COMMON_TYPES {
+            let mut checked = selected.iter().any(|t| t == file_type);
+            if ui.checkbox(&mut checked, file_type).changed() {
+                if checked {
+                    selected.push(file_type.to_string());
+                } else {
+                    selected.retain(|t| t != file_type);
+                }
+            }
+        }
+        if !selected.is_empty() && ui.button("Clear").clicked() {
+            selected.clear();
+            ui.close_menu();
+        }
+    });
+}
+
+/// Builds a monospace layout for `text` with every byte range in `submatches` highlighted.
+fn highlighted_line(ui: &egui::Ui, text: &str, submatches: &[(usize, usize)]) -> LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let normal = TextFormat {
+        font_id: font_id.clone(),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlight = TextFormat {
+        font_id,
+        color: egui::Color32::BLACK,
+        background: egui::Color32::YELLOW,
+        ..Default::default()
+    };
+
+    let mut job = LayoutJob::default();
+    for (range, matched) in highlight_spans(text, submatches) {
+        job.append(&text[range], 0.0, if matched { highlight.clone() } else { normal.clone() });
+    }
+    job
+}
+
+/// Splits `text` into consecutive byte ranges, each flagged whether it is inside a submatch.
+/// Ranges are clamped to the text and snapped to char boundaries, since rg reports byte offsets;
+/// overlapping ranges are merged rather than painted twice.
+fn highlight_spans(text: &str, submatches: &[(usize, usize)]) -> Vec<(std::ops::Range<usize>, bool)> {
+    let mut ranges = submatches.to_vec();
+    ranges.sort_unstable();
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (start, end) in ranges {
+        let start = floor_char_boundary(text, start.max(pos));
+        let end = floor_char_boundary(text, end);
+        if start >= end {
+            continue;
+        }
+        if pos < start {
+            spans.push((pos..start, false));
+        }
+        spans.push((start..end, true));
+        pos = end;
+    }
+    if pos < text.len() {
+        spans.push((pos..text.len(), false));
+    }
+    spans
+}
+
+/// Like `highlighted_line`, but starts from egui_extras' syntax coloring for `language` and
+/// paints the submatch ranges on top of it.
+fn syntax_highlighted_line(ui: &egui::Ui, text: &str, submatches: &[(usize, usize)], language: &str) -> LayoutJob {
+    let theme = egui_extras::syntax_highlighting::CodeTheme::from_style(ui.style());
+    let base = egui_extras::syntax_highlighting::highlight(ui.ctx(), &theme, text, language);
+    // The theme uses a fixed font size; match the plain rows so row heights stay uniform.
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+
+    let mut ranges: Vec<(usize, usize)> = submatches
+        .iter()
+        .map(|&(start, end)| (floor_char_boundary(text, start), floor_char_boundary(text, end)))
+        .filter(|(start, end)| start < end)
+        .collect();
+    ranges.sort_unstable();
+
+    let mut job = LayoutJob::default();
+    for section in &base.sections {
+        let mut pos = section.byte_range.start;
+        while pos < section.byte_range.end {
+            let mut format = section.format.clone();
+            format.font_id = font_id.clone();
+            // Either the match covering `pos`, or the plain stretch up to the next match.
+            let next = match ranges.iter().find(|&&(_, end)| end > pos) {
+                Some(&(start, end)) if start <= pos => {
+                    format.color = egui::Color32::BLACK;
+                    format.background = egui::Color32::YELLOW;
+                    end
+                }
+                Some(&(start, _)) => start,
+                None => section.byte_range.end,
+            }
+            .min(section.byte_range.end);
+            job.append(&base.text[pos..next], 0.0, format);
+            pos = next;
+        }
+    }
+    job
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails if any key of `fixture` (recursing into objects and arrays) is missing or changed
+    /// in `actual`, i.e. the field was renamed or stopped being persisted.
+    fn assert_keeps(fixture: &serde_json::Value, actual: &serde_json::Value, at: &str) {
+        match fixture {
+            serde_json::Value::Object(fields) => {
+                for (key, value) in fields {
+                    let at = format!("{}.{}", at, key);
+                    let actual = actual.get(key).unwrap_or_else(|| panic!("{} was not kept", at));
+                    assert_keeps(value, actual, &at);
+                }
+            }
+            serde_json::Value::Array(items) if items.iter().any(|item| item.is_object()) => {
+                assert_eq!(Some(items.len()), actual.as_array().map(Vec::len), "{}", at);
+                for (i, item) in items.iter().enumerate() {
+                    assert_keeps(item, &actual[i], &format!("{}[{}]", at, i));
+                }
+            }
+            _ => assert_eq!(fixture, actual, "{}", at),
         }
     }
+
+    #[test]
+    fn highlight_spans_cover_three_matches_on_one_line() {
+        let text = "foo bar foo baz foo";
+        let spans = highlight_spans(text, &[(16, 19), (0, 3), (8, 11)]);
+        let pieces: Vec<_> = spans.iter().map(|(range, matched)| (&text[range.clone()], *matched)).collect();
+        assert_eq!(
+            pieces,
+            vec![("foo", true), (" bar ", false), ("foo", true), (" baz ", false), ("foo", true)],
+        );
+    }
+
+    #[test]
+    fn highlight_spans_merge_overlaps_and_snap_to_chars() {
+        let text = "aé bc";
+        // 2 is inside "é"; (1, 4) and (3, 6) overlap; 99 is past the end.
+        let spans = highlight_spans(text, &[(3, 6), (1, 2), (1, 4), (5, 99)]);
+        let pieces: Vec<_> = spans.iter().map(|(range, matched)| (&text[range.clone()], *matched)).collect();
+        assert_eq!(pieces, vec![("a", false), ("é ", true), ("bc", true)]);
+    }
+
+    #[test]
+    fn app_keeps_every_persisted_field_through_serde() {
+        let fixture: serde_json::Value = serde_json::from_str(r#"{
+            "active_tab": 0,
+            "tabs": [{
+                "paths": ["/src"],
+                "result_sort": "LineNumber",
+                "group_by_file": true,
+                "relative_paths": true,
+                "show_timings": true,
+                "row_density": "Compact",
+                "result_font": "Proportional",
+                "result_font_size": 16.0,
+                "wrap_lines": true,
+                "auto_scroll": true,
+                "syntax_highlighting": true,
+                "show_offsets": true,
+                "time_limit_secs": 30,
+                "max_count_per_file": 4,
+                "case_mode": "Sensitive",
+                "search_hidden": true,
+                "follow_symlinks": true,
+                "ignore_level": "NoIgnore",
+                "ignore_files": ["extra.ignore"],
+                "binary_mode": "Text",
+                "encoding": "latin1",
+                "limit_depth": true,
+                "max_depth": 3,
+                "max_filesize": "500K",
+                "limit_line_range": true,
+                "min_line": 10,
+                "max_line": 20,
+                "limit_threads": true,
+                "threads": 2,
+                "include_globs": ["*.rs"],
+                "exclude_globs": ["*.log"],
+                "glob_case_insensitive": true,
+                "types": ["rust"],
+                "types_not": ["toml"],
+                "fixed_string": true,
+                "literal_block": true,
+                "whole_word": true,
+                "multiline": true,
+                "whole_file_pattern": true,
+                "multiline_dotall": true,
+                "pcre2": true,
+                "invert_match": true,
+                "only_matching": true,
+                "count_mode": true,
+                "filename_mode": true,
+                "context_before": 1,
+                "context_after": 2,
+                "max_results": 7,
+                "max_line_chars": 80,
+                "extra_args": "--no-messages",
+                "live_search": true,
+                "rerun_on_focus": true,
+                "debounce_interval": { "secs": 1, "nanos": 5 }
+            }],
+            "settings": {
+                "editor_command": "vim +{line} {path}",
+                "custom_command": "wezterm start --cwd {dir}",
+                "rg_path": "/opt/rg",
+                "query_history": ["foo"],
+                "recent_paths": ["/src"],
+                "saved_searches": [{
+                    "name": "todo",
+                    "query": "TODO",
+                    "paths": ["/src"],
+                    "options": { "whole_word": true },
+                    "literal_block": true,
+                    "whole_file_pattern": true
+                }],
+                "dark_mode": false
+            }
+        }"#).unwrap();
+        let app: MyApp = serde_json::from_value(fixture.clone()).unwrap();
+        let round_trip = serde_json::to_value(&app).unwrap();
+        assert_keeps(&fixture, &round_trip, "app");
+    }
 }
@@ -1 +1,5 @@
+pub mod editor;
+pub mod export;
+#[allow(clippy::module_inception)]
 pub mod gui;
+pub mod preview;
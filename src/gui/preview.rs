@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+/// Files larger than this are not loaded into the preview pane.
+const MAX_PREVIEW_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// The cache is dropped wholesale once it holds this many files.
+const MAX_CACHED_FILES: usize = 64;
+
+/// Caches file contents (split into lines) for the preview pane, keyed by path.
+#[derive(Default)]
+pub struct PreviewCache {
+    files: HashMap<String, Result<Vec<String>, String>>,
+}
+
+impl PreviewCache {
+    pub fn lines(&mut self, path: &str) -> &Result<Vec<String>, String> {
+        if !self.files.contains_key(path) && self.files.len() >= MAX_CACHED_FILES {
+            self.files.clear();
+        }
+        self.files.entry(path.to_string()).or_insert_with(|| read_lines(path))
+    }
+
+    /// Caches contents that don't come from a file on disk, such as piped input.
+    pub fn insert(&mut self, path: &str, bytes: &[u8]) {
+        self.files.insert(path.to_string(), Ok(String::from_utf8_lossy(bytes).lines().map(String::from).collect()));
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+}
+
+fn read_lines(path: &str) -> Result<Vec<String>, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    if metadata.len() > MAX_PREVIEW_FILE_SIZE {
+        return Err(format!("{} is too large to preview ({} bytes).", path, metadata.len()));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    Ok(String::from_utf8_lossy(&bytes).lines().map(String::from).collect())
+}
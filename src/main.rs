@@ -1,11 +1,29 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+mod cli;
 mod gui;
 mod ripgrep;
 
+use cli::cli::{read_piped_stdin, LaunchArgs, USAGE};
 use gui::gui::MyApp;
 
 fn main() -> Result<(), eframe::Error> {
+    let mut launch = match LaunchArgs::parse(std::env::args().skip(1)) {
+        Ok(launch) if launch.help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("rs-fzf: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if launch.read_stdin {
+        launch.stdin = read_piped_stdin();
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 600.0]),
         ..Default::default()
@@ -14,6 +32,6 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "fzf",
         options,
-        Box::new(|_cc| Box::<MyApp>::default()),
+        Box::new(|cc| Box::new(MyApp::new(cc, launch))),
     )
 }
@@ -1,7 +1,11 @@
 use crossbeam_channel::Sender;
-use serde::Deserialize;
-use std::io::{BufRead, BufReader};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 
 #[derive(Deserialize, Debug)]
@@ -34,14 +38,142 @@ pub struct Match {
 }
 
 
+// rg encodes data as either `{"text": "..."}` or, for invalid UTF-8, `{"bytes": "<base64>"}`.
 #[derive(Deserialize, Debug, Clone)]
-#[serde(untagged)]
+#[serde(rename_all = "snake_case")]
 enum TextOrBytes {
     Text(String),
-    Bytes(Vec<u8>),
+    Bytes(#[serde(deserialize_with = "deserialize_base64")] Vec<u8>),
+}
+
+fn deserialize_base64<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    decode_base64(&encoded).ok_or_else(|| serde::de::Error::custom("invalid base64 in rg output"))
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.bytes().filter(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Line text suitable for a label: lossily decoded, without the trailing newline, and with
+/// control characters (e.g. NUL bytes from binary data) replaced by a same-width `.` so
+/// submatch byte offsets stay valid.
+fn display_text(data: &TextData) -> String {
+    sanitize_line(&data.text_or_bytes.to_string_lossy())
+}
+
+/// The matched lines lossily decoded, with each submatch's byte range moved to where it landed
+/// in the decoded text: every invalid sequence becomes a 3-byte U+FFFD, shifting what follows.
+fn decoded_lines(m: &Match) -> (String, Vec<(usize, usize)>) {
+    let bytes = match &m.lines.text_or_bytes {
+        TextOrBytes::Text(text) => return (text.clone(), m.submatches.iter().map(|sm| (sm.start, sm.end)).collect()),
+        TextOrBytes::Bytes(bytes) => bytes,
+    };
+    let mut text = String::with_capacity(bytes.len());
+    // `(raw start, raw length, decoded start, valid)` of each stretch, in order.
+    let mut stretches = Vec::new();
+    let mut raw = 0;
+    for chunk in bytes.utf8_chunks() {
+        stretches.push((raw, chunk.valid().len(), text.len(), true));
+        text.push_str(chunk.valid());
+        raw += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            stretches.push((raw, chunk.invalid().len(), text.len(), false));
+            text.push(char::REPLACEMENT_CHARACTER);
+            raw += chunk.invalid().len();
+        }
+    }
+    let remap = |offset: usize| match stretches.iter().rev().find(|&&(start, _, _, _)| start <= offset) {
+        Some(&(start, len, decoded, valid)) if offset < start + len => if valid { decoded + offset - start } else { decoded },
+        _ => text.len(),
+    };
+    let submatches = m.submatches.iter().map(|sm| (remap(sm.start), remap(sm.end))).collect();
+    (text, submatches)
+}
+
+fn sanitize_line(text: &str) -> String {
+    text.trim_end()
+        .chars()
+        .map(|c| if c.is_control() && c != '\t' && c != '\n' { '.' } else { c })
+        .collect()
+}
+
+/// Cuts `text` down to `max_chars` chars (0 = no limit), starting a little before the first
+/// submatch so it stays visible, and marks the cut ends with an ellipsis. Submatch byte ranges
+/// are shifted to the new text; parts falling outside the window are clipped or dropped.
+fn truncate_line(text: String, submatches: Vec<(usize, usize)>, max_chars: usize) -> (String, Vec<(usize, usize)>) {
+    if max_chars == 0 || text.len() <= max_chars || text.chars().count() <= max_chars {
+        return (text, submatches);
+    }
+    let mut anchor = submatches.first().map_or(0, |&(start, _)| start).min(text.len());
+    while !text.is_char_boundary(anchor) {
+        anchor -= 1;
+    }
+    let lead = max_chars / 4;
+    let start = text[..anchor].char_indices().rev().take(lead).last().map_or(anchor, |(i, _)| i);
+    let end = text[start..].char_indices().nth(max_chars).map_or(text.len(), |(i, _)| start + i);
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < text.len() { "…" } else { "" };
+    let shift = prefix.len();
+    let truncated = format!("{}{}{}", prefix, &text[start..end], suffix);
+    let submatches = submatches
+        .into_iter()
+        .filter(|&(s, e)| e > start && s < end)
+        .map(|(s, e)| (s.max(start) - start + shift, e.min(end) - start + shift))
+        .collect();
+    (truncated, submatches)
+}
+
+/// rg's JSON output reports `--replace` results per submatch rather than rewriting the line,
+/// so the preview line is rebuilt here by splicing each replacement in. Submatches from rg
+/// versions that don't report replacements are filled in by `Replacer` first.
+fn replaced_text(m: &Match) -> String {
+    let (line, ranges) = decoded_lines(m);
+    let mut out = String::with_capacity(line.len());
+    let mut pos = 0;
+    for (sm, (start, end)) in m.submatches.iter().zip(ranges) {
+        let (Some(before), Some(replacement)) = (line.get(pos..start), &sm.replacement) else {
+            continue;
+        };
+        out.push_str(before);
+        out.push_str(&replacement.text_or_bytes.to_string_lossy());
+        pos = end;
+    }
+    out.push_str(line.get(pos..).unwrap_or_default());
+    sanitize_line(&out)
 }
 
 impl TextOrBytes {
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        String::from_utf8(bytes).map_or_else(|e| TextOrBytes::Bytes(e.into_bytes()), TextOrBytes::Text)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            TextOrBytes::Text(s) => s.as_bytes(),
+            TextOrBytes::Bytes(b) => b,
+        }
+    }
+
     fn to_string_lossy(&self) -> String {
         match self {
             TextOrBytes::Text(s) => s.clone(),
@@ -67,6 +199,7 @@ pub struct TextData {
 pub struct SubMatch {
     #[serde(rename = "match")]
     m: TextData,
+    replacement: Option<TextData>,
     start: usize,
     end: usize,
 }
@@ -117,125 +250,859 @@ pub struct DurationData {
     human: String,
 }
 
+impl DurationData {
+    fn to_duration(&self) -> Duration {
+        Duration::new(self.secs, self.nanos)
+    }
+}
+
+
 
+#[derive(Debug, Clone)]
+pub struct ContextLine {
+    pub line_number: u64,
+    pub text: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct GuiMatch { 
     pub path: String,
     pub line_number: u64,
+    /// 1-based character column of the first submatch, when there is one.
+    pub column: Option<u64>,
     pub line_text: String,
+    pub submatches: Vec<(usize, usize)>,
+    pub context_before: Vec<ContextLine>,
+    pub context_after: Vec<ContextLine>,
+    /// Set on the marker entry emitted for a binary file that matched, instead of its raw content.
+    pub binary_offset: Option<u64>,
+    /// Byte offset of the first submatch from the start of the file (of the binary marker for
+    /// binary files); `None` for count and file name rows.
+    pub absolute_offset: Option<u64>,
+    /// Set instead of line details when searching in count-only mode.
+    pub match_count: Option<u64>,
+    /// Set in filename mode, where the query matched the path itself: `line_text` holds the
+    /// path and `submatches` the matched ranges in it.
+    pub name_match: bool,
 }
 
 
+#[derive(Debug, Clone)]
+pub struct SearchStats {
+    pub files_searched: u64,
+    pub bytes_searched: u64,
+    pub matched_lines: u64,
+    pub matches: u64,
+    pub elapsed: String,
+}
+
 pub enum SearchResult {
-    Match(GuiMatch), 
+    /// Matches are delivered in batches to keep channel traffic down on dense searches.
+    Batch(Vec<GuiMatch>),
+    Stats(SearchStats),
     Error(String),
+    /// The configured rg executable (carried here) could not be found.
+    RgNotFound(String),
     Done,
+    /// The search was stopped early after emitting this many matches.
+    Capped(usize),
+    /// Non-fatal problems sent before `Done`: rg stderr lines (e.g. permission denied on some
+    /// files) and a summary of output lines that failed to parse.
+    Warnings(Vec<String>),
+    /// Periodic count of rg `begin` messages so far. rg only emits those for files with at
+    /// least one match, so this tracks matching files rather than every file opened.
+    Progress { files_scanned: u64 },
+    /// Time rg spent on each matching file, from its `end` message. Only sent when
+    /// `RgOptions::file_timings` is set.
+    FileTimings(Vec<(String, Duration)>),
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CaseMode {
+    /// `-s`: always match case.
+    Sensitive,
+    /// `-i`: never match case.
+    Insensitive,
+    /// `-S`: ignore case unless the query contains an uppercase letter.
+    #[default]
+    Smart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IgnoreLevel {
+    /// Honor .gitignore, .ignore and .rgignore files (rg's default).
+    #[default]
+    Respect,
+    /// `--no-ignore`: don't read ignore files, but still skip hidden and binary files.
+    NoIgnore,
+    /// `-uu`: ignore nothing and search hidden files too.
+    Unrestricted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BinaryMode {
+    /// rg's default: skip binary files found while recursing.
+    #[default]
+    Skip,
+    /// `-a`: search binary files as if they were text.
+    Text,
+    /// `--binary`: search binary files but report them instead of printing their content.
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RgSort {
+    /// Unsorted, parallel search (rg's default).
+    #[default]
+    None,
+    /// `--sort path`
+    Path,
+    /// `--sortr modified`: most recently modified files first.
+    ModifiedNewest,
+}
+
+/// Serializable so saved searches can snapshot it; `default` lets older snapshots load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RgOptions {
-     pub case_insensitive: bool,
+     pub rg_path: String,
+     pub case_mode: CaseMode,
      pub search_hidden: bool,
      pub follow_symlinks: bool,
-     pub globs: Option<String>,
+     pub ignore_level: IgnoreLevel,
+     /// Each becomes its own `--ignore-file <path>`; these apply even with `--no-ignore`.
+     pub ignore_files: Vec<String>,
+     pub binary_mode: BinaryMode,
+     pub encoding: Option<String>,
+     pub max_depth: Option<u32>,
+     /// `--max-filesize`, in rg's size syntax (see `is_valid_filesize`).
+     pub max_filesize: Option<String>,
+     /// `-j N`; `None` leaves the thread count to rg.
+     pub threads: Option<u32>,
+     /// Passed as `-g` as-is.
+     pub include_globs: Vec<String>,
+     /// Passed as `-g` with a `!` prepended (unless already present).
+     pub exclude_globs: Vec<String>,
+     pub glob_case_insensitive: bool,
+     /// Each passed as `--type`; rg searches files matching any of them.
+     pub types: Vec<String>,
+     /// Each passed as `--type-not`; combines with `types`, e.g. `--type rust --type-not toml`.
+     pub types_not: Vec<String>,
+     pub fixed_string: bool,
+     pub whole_word: bool,
+     pub multiline: bool,
+     pub multiline_dotall: bool,
+     pub pcre2: bool,
+     pub invert_match: bool,
+     /// `-o`. rg's JSON output still reports whole lines, so each submatch is split out here.
+     pub only_matching: bool,
+     pub replace: Option<String>,
+     pub sort: RgSort,
+     pub count_mode: bool,
+     pub context_before: u32,
+     pub context_after: u32,
+     /// `--max-count`: rg stops reading a file after this many matching lines. With `-o` a
+     /// line can still yield several rows.
+     pub max_count_per_file: Option<u32>,
+     /// Stop the search after this many matches in total; 0 means unlimited.
+     pub max_results: usize,
+     /// Raw flags appended after everything the UI builds; rg reports any it rejects on stderr.
+     pub extra_args: Vec<String>,
+     /// Longest line, in chars, kept for display; longer ones are cut around the first match.
+     /// 0 means unlimited. rg's own `--max-columns` has no effect on `--json` output.
+     pub max_line_chars: usize,
+     /// Forward per-file elapsed times as `SearchResult::FileTimings`. Doesn't change the rg command.
+     pub file_timings: bool,
+     /// List files with `rg --files` and match the query against their paths instead of contents.
+     pub filename_mode: bool,
+     /// Keep only matches starting on these lines (inclusive). rg has no such flag, so matches
+     /// outside the range are dropped as they're read; rg still searches whole files.
+     pub line_range: Option<(u64, u64)>,
+}
+
+
+
+/// Parses a `path:count` line from `rg --count-matches --with-filename`.
+fn parse_count_line(line: &str) -> Option<GuiMatch> {
+    let (path, count) = line.rsplit_once(':')?;
+    let count: u64 = count.trim().parse().ok()?;
+    Some(GuiMatch {
+        path: path.to_string(),
+        line_number: 0,
+        column: None,
+        line_text: format!("{} matches", count),
+        submatches: Vec::new(),
+        context_before: Vec::new(),
+        context_after: Vec::new(),
+        binary_offset: None,
+        absolute_offset: None,
+        match_count: Some(count),
+        name_match: false,
+    })
 }
 
+/// Converts the first submatch's byte offset into a 1-based character column on the line
+/// it starts on (multiline matches may start after embedded newlines).
+fn first_match_column(m: &Match) -> Option<u64> {
+    let (lines, ranges) = decoded_lines(m);
+    column_at(&lines, ranges.first()?.0)
+}
 
+fn column_at(lines: &str, start: usize) -> Option<u64> {
+    let prefix = lines.get(..start)?;
+    let line_start = prefix.rfind('\n').map_or(0, |i| i + 1);
+    Some(prefix[line_start..].chars().count() as u64 + 1)
+}
 
-pub fn run_ripgrep(query: String, path: String, options: RgOptions, sender: Sender<SearchResult>) {
-    let mut cmd_args = vec![
-        "--json".to_string(),
-        query, 
-        path,  
-    ];
+/// The result row for one rg `match` message, without context (that arrives in separate
+/// messages). Inverted matches have no submatches and come through unhighlighted.
+fn match_row(m: &Match, options: &RgOptions) -> GuiMatch {
+    // Submatch offsets refer to the original line, so they can't be highlighted in a
+    // replacement preview.
+    let (line_text, submatches) = if options.replace.is_some() {
+        (replaced_text(m), Vec::new())
+    } else {
+        let (lines, ranges) = decoded_lines(m);
+        (sanitize_line(&lines), ranges)
+    };
+    let (line_text, submatches) = truncate_line(line_text, submatches, options.max_line_chars);
+    GuiMatch {
+        path: m.path.text_or_bytes.to_string_lossy(),
+        line_number: m.line_number.unwrap_or(0),
+        column: first_match_column(m),
+        line_text,
+        submatches,
+        context_before: Vec::new(),
+        context_after: Vec::new(),
+        binary_offset: None,
+        absolute_offset: Some(m.absolute_offset + m.submatches.first().map_or(0, |sm| sm.start as u64)),
+        match_count: None,
+        name_match: false,
+    }
+}
 
-    
-    if options.case_insensitive {
-        cmd_args.push("-i".to_string());
+/// One row per submatch holding just the matched (or replaced) text, like `rg -o` prints.
+fn only_matching_rows(m: &Match) -> Vec<GuiMatch> {
+    let (lines, ranges) = decoded_lines(m);
+    m.submatches
+        .iter()
+        .zip(ranges)
+        .map(|(sm, (start, _))| {
+            let text = sm.replacement.as_ref().unwrap_or(&sm.m);
+            let line_text = sanitize_line(&text.text_or_bytes.to_string_lossy());
+            let newlines_before = lines.get(..start).map_or(0, |prefix| prefix.matches('\n').count());
+            GuiMatch {
+                path: m.path.text_or_bytes.to_string_lossy(),
+                line_number: m.line_number.unwrap_or(0) + newlines_before as u64,
+                column: column_at(&lines, start),
+                submatches: if sm.replacement.is_some() { Vec::new() } else { vec![(0, line_text.len())] },
+                line_text,
+                context_before: Vec::new(),
+                context_after: Vec::new(),
+                binary_offset: None,
+                absolute_offset: Some(m.absolute_offset + sm.start as u64),
+                match_count: None,
+                name_match: false,
+            }
+        })
+        .collect()
+}
+
+fn last_line_number(m: &GuiMatch) -> u64 {
+    m.line_number + m.line_text.lines().count().saturating_sub(1) as u64
+}
+
+const BATCH_SIZE: usize = 256;
+const BATCH_INTERVAL: Duration = Duration::from_millis(16);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a cancel is checked for while rg is quiet, e.g. walking a tree with no matches.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Coalesces matches into `SearchResult::Batch` messages, flushing when the batch is full
+/// or has been held for longer than `BATCH_INTERVAL`.
+struct MatchBatcher<'a> {
+    sender: &'a Sender<SearchResult>,
+    batch: Vec<GuiMatch>,
+    last_flush: Instant,
+    /// Set once a send fails because the GUI dropped the receiver, e.g. for a newer search.
+    hung_up: bool,
+}
+
+impl<'a> MatchBatcher<'a> {
+    fn new(sender: &'a Sender<SearchResult>) -> Self {
+        MatchBatcher { sender, batch: Vec::with_capacity(BATCH_SIZE), last_flush: Instant::now(), hung_up: false }
+    }
+
+    /// Returns false once the GUI side has hung up.
+    fn push(&mut self, gui_match: GuiMatch) -> bool {
+        self.batch.push(gui_match);
+        if self.batch.len() >= BATCH_SIZE || self.last_flush.elapsed() >= BATCH_INTERVAL {
+            return self.flush();
+        }
+        true
+    }
+
+    fn flush(&mut self) -> bool {
+        self.last_flush = Instant::now();
+        if self.batch.is_empty() {
+            return true;
+        }
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(BATCH_SIZE));
+        if self.sender.send(SearchResult::Batch(batch)).is_err() {
+            self.hung_up = true;
+            return false;
+        }
+        true
+    }
+}
+
+/// Builds the arguments `run_ripgrep` passes to rg (everything after the program name), so
+/// the GUI can show the exact command without running it.
+pub fn build_rg_args(query: &str, paths: &[String], options: &RgOptions) -> Vec<String> {
+    let mut cmd_args = if options.filename_mode {
+        // The query isn't given to rg; `run_ripgrep` matches it against the listed paths.
+        vec!["--files".to_string()]
+    } else if options.count_mode {
+        // --count-matches overrides --json, so count mode parses rg's plain `path:count` output instead.
+        vec!["--count-matches".to_string(), "--with-filename".to_string(), "-e".to_string(), query.to_string()]
+    } else {
+        vec!["--json".to_string(), "-e".to_string(), query.to_string()]
+    };
+    // `-e` above keeps a query like `-foo` from being read as flags. Each search root is its
+    // own positional argument.
+    cmd_args.extend(paths.iter().cloned());
+    // Flags that only affect how file contents are matched and printed.
+    if !options.filename_mode {
+        cmd_args.push(match options.case_mode {
+            CaseMode::Sensitive => "-s",
+            CaseMode::Insensitive => "-i",
+            CaseMode::Smart => "-S",
+        }.to_string());
+        match options.binary_mode {
+            BinaryMode::Skip => {}
+            BinaryMode::Text => cmd_args.push("-a".to_string()),
+            BinaryMode::Binary => cmd_args.push("--binary".to_string()),
+        }
+        if let Some(encoding) = &options.encoding {
+            cmd_args.push("--encoding".to_string());
+            cmd_args.push(encoding.clone());
+        }
+        if options.fixed_string {
+            cmd_args.push("-F".to_string());
+        }
+        if options.whole_word {
+            cmd_args.push("-w".to_string());
+        }
+        if options.multiline {
+            cmd_args.push("-U".to_string());
+        }
+        if options.multiline_dotall {
+            cmd_args.push("--multiline-dotall".to_string());
+        }
+        if options.pcre2 {
+            cmd_args.push("-P".to_string());
+        }
+        if options.invert_match {
+            cmd_args.push("-v".to_string());
+        }
+        if options.only_matching {
+            cmd_args.push("-o".to_string());
+        }
+        if let Some(replace) = &options.replace {
+            cmd_args.push("-r".to_string());
+            cmd_args.push(replace.clone());
+        }
+        if options.context_before > 0 {
+            cmd_args.push("-B".to_string());
+            cmd_args.push(options.context_before.to_string());
+        }
+        if options.context_after > 0 {
+            cmd_args.push("-A".to_string());
+            cmd_args.push(options.context_after.to_string());
+        }
+        if let Some(max_count) = options.max_count_per_file {
+            cmd_args.push("--max-count".to_string());
+            cmd_args.push(max_count.to_string());
+        }
     }
     if options.search_hidden {
         cmd_args.push("--hidden".to_string());
     }
-     if options.follow_symlinks {
+    if options.follow_symlinks {
         cmd_args.push("-L".to_string());
     }
-    if let Some(globs) = options.globs {
-        
-        
-        for glob in globs.split(|c| c == ',' || c == ';') {
-             let trimmed_glob = glob.trim();
-             if !trimmed_glob.is_empty() {
-                cmd_args.push("-g".to_string());
-                cmd_args.push(trimmed_glob.to_string());
-             }
+    match options.ignore_level {
+        IgnoreLevel::Respect => {}
+        IgnoreLevel::NoIgnore => cmd_args.push("--no-ignore".to_string()),
+        IgnoreLevel::Unrestricted => cmd_args.push("-uu".to_string()),
+    }
+    for ignore_file in &options.ignore_files {
+        let ignore_file = ignore_file.trim();
+        if !ignore_file.is_empty() {
+            cmd_args.push("--ignore-file".to_string());
+            cmd_args.push(ignore_file.to_string());
+        }
+    }
+    if let Some(depth) = options.max_depth {
+        cmd_args.push("--max-depth".to_string());
+        cmd_args.push(depth.to_string());
+    }
+    if let Some(size) = &options.max_filesize {
+        cmd_args.push("--max-filesize".to_string());
+        cmd_args.push(size.clone());
+    }
+    if let Some(threads) = options.threads {
+        cmd_args.push("-j".to_string());
+        cmd_args.push(threads.to_string());
+    }
+    match options.sort {
+        RgSort::None => {}
+        RgSort::Path => {
+            cmd_args.push("--sort".to_string());
+            cmd_args.push("path".to_string());
+        }
+        RgSort::ModifiedNewest => {
+            cmd_args.push("--sortr".to_string());
+            cmd_args.push("modified".to_string());
+        }
+    }
+    for glob in &options.include_globs {
+        let trimmed_glob = glob.trim();
+        if !trimmed_glob.is_empty() {
+            cmd_args.push("-g".to_string());
+            cmd_args.push(trimmed_glob.to_string());
         }
     }
+    for glob in &options.exclude_globs {
+        let trimmed_glob = glob.trim().trim_start_matches('!');
+        if !trimmed_glob.is_empty() {
+            cmd_args.push("-g".to_string());
+            cmd_args.push(format!("!{}", trimmed_glob));
+        }
+    }
+    if options.glob_case_insensitive {
+        cmd_args.push("--glob-case-insensitive".to_string());
+    }
+    for file_type in &options.types {
+        cmd_args.push("--type".to_string());
+        cmd_args.push(file_type.clone());
+    }
+    for file_type in &options.types_not {
+        cmd_args.push("--type-not".to_string());
+        cmd_args.push(file_type.clone());
+    }
+    // Last, so a flag missing its value errors out instead of swallowing the query or a path.
+    cmd_args.extend(options.extra_args.iter().cloned());
+    cmd_args
+}
+
+/// The full invocation as one line, quoted so it can be pasted into a shell.
+pub fn command_line(rg_path: &str, args: &[String]) -> String {
+    std::iter::once(rg_path).chain(args.iter().map(String::as_str)).map(shell_quote).collect::<Vec<_>>().join(" ")
+}
+
+/// Quotes one argument so the platform's shell passes it through unchanged.
+#[cfg(not(windows))]
+pub fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '=' | ':' | ',' | '+' | '@' | '%')) {
+        return arg.to_string();
+    }
+    // Nothing is special inside POSIX single quotes except the quote itself, which has to be
+    // closed, escaped, and reopened.
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Quotes one argument so the platform's shell passes it through unchanged.
+#[cfg(windows)]
+pub fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '&' | '|' | '<' | '>' | '^' | '%' | '(' | ')' | '!')) {
+        return arg.to_string();
+    }
+    // CommandLineToArgvW rules: backslashes are literal unless they precede a quote, in which
+    // case they (and the quote) need escaping.
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            c => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Whether `size` is something rg accepts for `--max-filesize`: a byte count with an optional
+/// K, M or G suffix, like `500K` or `10M`.
+pub fn is_valid_filesize(size: &str) -> bool {
+    let digits = size.strip_suffix(['K', 'M', 'G']).unwrap_or(size);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The query as a regex pattern that matches the way rg would: the case, fixed-string,
+/// whole-word and dot-matches-newline options become inline flags, and `^`/`$` match at
+/// every line like in rg.
+fn query_pattern(query: &str, options: &RgOptions) -> String {
+    let pattern = if options.fixed_string { regex::escape(query) } else { query.to_string() };
+    let pattern = if options.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+    let case_insensitive = match options.case_mode {
+        CaseMode::Sensitive => false,
+        CaseMode::Insensitive => true,
+        CaseMode::Smart => !query.chars().any(char::is_uppercase),
+    };
+    let dotall = options.multiline && options.multiline_dotall;
+    format!("(?m{}{}){}", if case_insensitive { "i" } else { "" }, if dotall { "s" } else { "" }, pattern)
+}
+
+/// Compiles the query for matching file paths in filename mode.
+fn file_name_regex(query: &str, options: &RgOptions) -> Result<regex::Regex, String> {
+    regex::Regex::new(&query_pattern(query, options)).map_err(|e| format!("Invalid file name pattern: {}", e))
+}
 
+/// Fills in `--replace` results for rg versions whose `--json` output leaves each submatch's
+/// `replacement` out (ripgrep 14 and older), by re-running the query on the matched line and
+/// expanding `$1`/`$name` in the template.
+struct Replacer {
+    /// `None` when the query isn't valid for the regex crate (e.g. PCRE2 lookaround); the
+    /// template is then spliced in unexpanded.
+    regex: Option<regex::bytes::Regex>,
+    template: String,
+}
+
+impl Replacer {
+    fn new(query: &str, options: &RgOptions) -> Option<Self> {
+        let template = options.replace.clone()?;
+        let regex = regex::bytes::Regex::new(&query_pattern(query, options)).ok();
+        Some(Replacer { regex, template })
+    }
+
+    fn fill(&self, m: &mut Match) {
+        let line = m.lines.text_or_bytes.as_bytes();
+        for sm in m.submatches.iter_mut().filter(|sm| sm.replacement.is_none()) {
+            let captures = self
+                .regex
+                .as_ref()
+                .and_then(|regex| regex.captures_at(line, sm.start))
+                .filter(|caps| caps.get(0).is_some_and(|whole| whole.start() == sm.start));
+            let mut replacement = Vec::new();
+            match captures {
+                Some(caps) => caps.expand(self.template.as_bytes(), &mut replacement),
+                None => replacement.extend_from_slice(self.template.as_bytes()),
+            }
+            sm.replacement = Some(TextData { text_or_bytes: TextOrBytes::from_bytes(replacement) });
+        }
+    }
+}
 
-    let child = Command::new("rg")
+/// The path rg reports for matches read from standard input.
+pub const STDIN_PATH: &str = "<stdin>";
+
+/// When `input` is set it is piped to rg's stdin, which rg searches when `-` is among `paths`.
+pub fn run_ripgrep(
+    query: String,
+    paths: Vec<String>,
+    options: RgOptions,
+    input: Option<Arc<Vec<u8>>>,
+    sender: Sender<SearchResult>,
+    cancel: Arc<AtomicBool>,
+) {
+    let name_filter = match options.filename_mode.then(|| file_name_regex(&query, &options)).transpose() {
+        Ok(filter) => filter,
+        Err(e) => {
+            sender.send(SearchResult::Error(e)).ok();
+            return;
+        }
+    };
+    let replacer = Replacer::new(&query, &options);
+    let cmd_args = build_rg_args(&query, &paths, &options);
+    // Shown alongside failures so the exact invocation can be rerun in a terminal.
+    let invocation = command_line(&options.rg_path, &cmd_args);
+    let child = Command::new(&options.rg_path)
         .args(&cmd_args)
+        // Without piped input, keep rg from falling back to reading the app's own stdin.
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
         .stdout(Stdio::piped())
         .stderr(Stdio::piped()) 
         .spawn();
 
     match child {
         Ok(mut child) => {
+            let mut capped = false;
+            let hung_up;
+            // Set once rg has produced output showing it actually searched, which tells
+            // per-file errors apart from fatal ones (bad flags, bad pattern) on exit code 2.
+            let mut completed = false;
+            // Drain stderr concurrently so a flood of per-file errors can't fill the pipe and
+            // stall rg while stdout is still being read.
+            let mut stderr_reader = child.stderr.take().map(|stderr| {
+                std::thread::spawn(move || BufReader::new(stderr).lines().map_while(Result::ok).collect::<Vec<String>>())
+            });
+            // Fed from its own thread so rg can't block on a full stdout pipe while we're still
+            // writing. A write error just means rg stopped reading early (a cap or a cancel).
+            if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+                std::thread::spawn(move || {
+                    let _ = stdin.write_all(&input);
+                });
+            }
             if let Some(stdout) = child.stdout.take() {
-                let reader = BufReader::new(stdout);
-                for line_result in reader.lines() {
-                    match line_result {
-                        Ok(line) => {
+                // Read on a separate thread so a cancel is noticed even while rg prints nothing.
+                // The thread ends once rg is killed or `lines` is dropped below.
+                let (line_sender, lines) = crossbeam_channel::bounded::<std::io::Result<String>>(BATCH_SIZE);
+                std::thread::spawn(move || {
+                    for line in BufReader::new(stdout).lines() {
+                        if line_sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                });
+                // A match is held back until its trailing context lines have arrived,
+                // and context lines preceding a match are buffered until that match shows up.
+                let mut pending_match: Option<GuiMatch> = None;
+                let mut pending_context: Vec<ContextLine> = Vec::new();
+                let mut match_count = 0;
+                let mut files_scanned = 0;
+                // Unparseable lines are tallied and reported once rather than per line.
+                let mut parse_failures = 0;
+                let mut first_parse_error: Option<String> = None;
+                let mut last_progress = Instant::now();
+                let mut batcher = MatchBatcher::new(&sender);
+                let mut file_timings: Vec<(String, Duration)> = Vec::new();
+                loop {
+                    let line_result = match lines.recv_timeout(CANCEL_POLL_INTERVAL) {
+                        Ok(line_result) => line_result,
+                        Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                            if cancel.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            continue;
+                        }
+                        Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+                    };
+                    if cancel.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    match (line_result, &name_filter) {
+                        (Ok(line), Some(filter)) => {
+                            completed = true;
+                            let submatches: Vec<(usize, usize)> = filter.find_iter(&line).map(|m| (m.start(), m.end())).collect();
+                            if submatches.is_empty() {
+                                continue;
+                            }
+                            if options.max_results > 0 && match_count >= options.max_results {
+                                capped = true;
+                                break;
+                            }
+                            match_count += 1;
+                            let gui_match = GuiMatch {
+                                path: line.clone(),
+                                line_number: 0,
+                                column: None,
+                                line_text: line,
+                                submatches,
+                                context_before: Vec::new(),
+                                context_after: Vec::new(),
+                                binary_offset: None,
+                                absolute_offset: None,
+                                match_count: None,
+                                name_match: true,
+                            };
+                            if !batcher.push(gui_match) {
+                                break;
+                            }
+                        }
+                        (Ok(line), None) if options.count_mode => {
+                            if let Some(gui_match) = parse_count_line(&line) {
+                                completed = true;
+                                if !batcher.push(gui_match) {
+                                    break;
+                                }
+                            }
+                        }
+                        (Ok(line), None) => {
                             match serde_json::from_str::<RgJsonItem>(&line) {
-                                Ok(RgJsonItem::Match(m)) => {
-                                    
-                                    let gui_match = GuiMatch {
-                                        path: m.path.text_or_bytes.to_string_lossy(),
-                                        line_number: m.line_number.unwrap_or(0), 
-                                        line_text: m.lines.text_or_bytes.to_string_lossy().trim_end().to_string(), 
+                                Ok(RgJsonItem::Match(m))
+                                    if options.line_range.is_some_and(|(min, max)| {
+                                        !(min..=max).contains(&m.line_number.unwrap_or(0))
+                                    }) =>
+                                {
+                                    pending_context.clear();
+                                }
+                                Ok(RgJsonItem::Match(mut m)) => {
+                                    if let Some(replacer) = &replacer {
+                                        replacer.fill(&mut m);
+                                    }
+                                    if options.max_results > 0 && match_count >= options.max_results {
+                                        capped = true;
+                                        break;
+                                    }
+                                    match_count += 1;
+                                    if let Some(prev) = pending_match.take()
+                                        && !batcher.push(prev)
+                                    {
+                                        break;
+                                    }
+                                    if options.only_matching {
+                                        pending_context.clear();
+                                        if !only_matching_rows(&m).into_iter().all(|row| batcher.push(row)) {
+                                            break;
+                                        }
+                                        continue;
+                                    }
+                                    // Pending lines further back are trailing context of a match that
+                                    // was dropped for its line range, not leading context of this one.
+                                    let first_context_line = m.line_number.unwrap_or(0).saturating_sub(u64::from(options.context_before));
+                                    pending_context.retain(|c| c.line_number >= first_context_line);
+                                    pending_match = Some(GuiMatch {
+                                        context_before: std::mem::take(&mut pending_context),
+                                        ..match_row(&m, &options)
+                                    });
+                                }
+                                Ok(RgJsonItem::Context(c)) => {
+                                    let context_line = ContextLine {
+                                        line_number: c.line_number.unwrap_or(0),
+                                        text: truncate_line(display_text(&c.lines), Vec::new(), options.max_line_chars).0,
                                     };
-                                    if sender.send(SearchResult::Match(gui_match)).is_err() {
-                                        eprintln!("GUI channel closed, stopping search thread.");
-                                        break; 
+                                    match pending_match.as_mut() {
+                                        // A multiline match ends on its last line, so trailing context starts after that.
+                                        Some(prev) if context_line.line_number <= last_line_number(prev) + u64::from(options.context_after) => {
+                                            prev.context_after.push(context_line);
+                                        }
+                                        _ => pending_context.push(context_line),
+                                    }
+                                }
+                                Ok(RgJsonItem::End(end)) => {
+                                    pending_context.clear();
+                                    if options.file_timings
+                                        && let Some(path) = &end.path
+                                    {
+                                        file_timings.push((path.text_or_bytes.to_string_lossy(), end.stats.elapsed.to_duration()));
+                                        if file_timings.len() >= BATCH_SIZE {
+                                            sender.send(SearchResult::FileTimings(std::mem::take(&mut file_timings))).ok();
+                                        }
+                                    }
+                                    if let Some(prev) = pending_match.take()
+                                        && !batcher.push(prev)
+                                    {
+                                        break;
                                     }
+                                    if let (Some(offset), Some(path)) = (end.binary_offset, end.path)
+                                        && end.stats.searches_with_match > 0
+                                        && !batcher.push(GuiMatch {
+                                            path: path.text_or_bytes.to_string_lossy(),
+                                            line_number: 0,
+                                            column: None,
+                                            line_text: format!("[binary file matches (found \"\\0\" byte around offset {})]", offset),
+                                            submatches: Vec::new(),
+                                            context_before: Vec::new(),
+                                            context_after: Vec::new(),
+                                            binary_offset: Some(offset),
+                                            absolute_offset: Some(offset),
+                                            match_count: None,
+                                            name_match: false,
+                                        })
+                                    {
+                                        break;
+                                    }
+                                }
+                                Ok(RgJsonItem::Summary(summary)) => {
+                                    let stats = SearchStats {
+                                        files_searched: summary.stats.searches,
+                                        bytes_searched: summary.stats.bytes_searched,
+                                        matched_lines: summary.stats.matched_lines,
+                                        matches: summary.stats.matches,
+                                        elapsed: summary.elapsed_total.human,
+                                    };
+                                    completed = true;
+                                    batcher.flush();
+                                    sender.send(SearchResult::Stats(stats)).ok();
                                 }
-                                Ok(RgJsonItem::Begin(_)) | Ok(RgJsonItem::End(_)) | Ok(RgJsonItem::Context(_)) | Ok(RgJsonItem::Summary(_)) => {
-                                    
+                                Ok(RgJsonItem::Begin(_)) => {
+                                    files_scanned += 1;
+                                    if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                                        last_progress = Instant::now();
+                                        sender.send(SearchResult::Progress { files_scanned }).ok();
+                                    }
                                 }
                                 Err(e) => {
-                                     eprintln!("Failed to parse rg JSON line: {}, line: {}", e, line);
-                                     
-                                     
+                                    parse_failures += 1;
+                                    first_parse_error.get_or_insert_with(|| e.to_string());
                                 }
                             }
                         }
-                        Err(e) => {
+                        (Err(e), _) => {
+                            batcher.flush();
                             sender.send(SearchResult::Error(format!("Error reading rg output: {}", e))).ok();
                             break;
                         }
                     }
                 }
+                if let Some(prev) = pending_match.take() {
+                    batcher.push(prev);
+                }
+                batcher.flush();
+                hung_up = batcher.hung_up;
+                if !file_timings.is_empty() {
+                    sender.send(SearchResult::FileTimings(file_timings)).ok();
+                }
+                if let Some(error) = first_parse_error {
+                    let warning = format!("{} output lines could not be parsed (first error: {}).", parse_failures, error);
+                    sender.send(SearchResult::Warnings(vec![warning])).ok();
+                }
             } else {
-                 sender.send(SearchResult::Error("Failed to capture rg stdout.".to_string())).ok();
+                child.kill().ok();
+                child.wait().ok();
+                let stderr_lines = stderr_reader.take().and_then(|reader| reader.join().ok()).unwrap_or_default();
+                let mut message = format!("Failed to capture rg stdout.\nCommand: {}", invocation);
+                if !stderr_lines.is_empty() {
+                    message.push_str("\nrg stderr:\n");
+                    message.push_str(&stderr_lines.iter().take(10).cloned().collect::<Vec<_>>().join("\n"));
+                }
+                sender.send(SearchResult::Error(message)).ok();
+                return;
+            }
+
+            if capped || hung_up || cancel.load(Ordering::Relaxed) {
+                // Kill and reap the child so a stopped or superseded search doesn't keep running
+                // or leave a zombie behind.
+                child.kill().ok();
+                child.wait().ok();
+                if capped {
+                    sender.send(SearchResult::Capped(options.max_results)).ok();
+                }
+                return;
             }
 
             
-            match child.wait_with_output() {
-                 Ok(output) => {
-                    if !output.status.success() {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        
-                        if !stderr.is_empty() {
-                             sender.send(SearchResult::Error(format!("rg exited with error: {}", stderr.trim()))).ok();
-                        } else if output.status.code().is_some() {
-                             sender.send(SearchResult::Error(format!("rg exited with status: {}", output.status))).ok();
-                        } else {
-                             sender.send(SearchResult::Error("rg exited with non-zero status.".to_string())).ok();
+            let status = child.wait();
+            let stderr_lines = stderr_reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+            match status {
+                 Ok(status) => {
+                    let stderr = stderr_lines.join("\n");
+                    // rg exits with 0 when something matched, 1 when nothing matched and 2 on error.
+                    if matches!(status.code(), Some(0) | Some(1)) || (completed && !stderr_lines.is_empty()) {
+                        if !stderr_lines.is_empty() {
+                            sender.send(SearchResult::Warnings(stderr_lines)).ok();
                         }
+                        sender.send(SearchResult::Done).ok();
+                    } else if options.pcre2 && stderr.contains("PCRE2 is not available") {
+                         sender.send(SearchResult::Error("PCRE2 (-P) is not available in your rg build. Install a ripgrep build with PCRE2 support or turn the PCRE2 option off.".to_string())).ok();
+                    } else if !stderr.is_empty() {
+                         sender.send(SearchResult::Error(format!("rg exited with error: {}\nCommand: {}", stderr.trim(), invocation))).ok();
+                    } else if status.code().is_some() {
+                         sender.send(SearchResult::Error(format!("rg exited with status: {}", status))).ok();
                     } else {
-                         
-                         sender.send(SearchResult::Done).ok();
+                         sender.send(SearchResult::Error("rg exited with non-zero status.".to_string())).ok();
                     }
                  }
                  Err(e) => {
@@ -245,13 +1112,431 @@ pub fn run_ripgrep(query: String, path: String, options: RgOptions, sender: Send
 
         }
         Err(e) => {
-            let err_msg = if e.kind() == std::io::ErrorKind::NotFound {
-                "Error: 'rg' command not found. Please ensure ripgrep is installed and in your PATH.".to_string()
+            if e.kind() == std::io::ErrorKind::NotFound {
+                sender.send(SearchResult::RgNotFound(options.rg_path)).ok();
             } else {
-                format!("Failed to spawn rg process: {}", e)
-            };
-            sender.send(SearchResult::Error(err_msg)).ok();
+                sender.send(SearchResult::Error(format!("Failed to spawn rg process: {}", e))).ok();
+            }
         }
     }
     
 }
+
+/// Splits `input` into arguments on whitespace, shell-style: single quotes are literal, double
+/// quotes allow `\"` and `\\` escapes, and a backslash outside quotes escapes the next char.
+pub fn split_args(input: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("Unterminated double quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                match chars.next() {
+                    Some(c) => current.push(c),
+                    None => return Err("Trailing backslash".to_string()),
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Inverse of `split_args`: joins `args` into one string, single-quoting any that need it.
+pub fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\')) {
+                arg.clone()
+            } else if !arg.contains('\'') {
+                format!("'{}'", arg)
+            } else {
+                format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Oldest ripgrep with `--json` output, which every search relies on.
+pub const MIN_RG_VERSION: (u32, u32, u32) = (0, 10, 0);
+/// `--glob-case-insensitive` arrived in ripgrep 11.
+pub const MIN_RG_GLOB_CASE_INSENSITIVE: (u32, u32, u32) = (11, 0, 0);
+
+/// What `rg --version` reports about the configured executable.
+#[derive(Debug, Clone)]
+pub struct RgInfo {
+    /// First line of the output, e.g. `ripgrep 14.1.0`.
+    pub version_line: String,
+    /// `None` when the line doesn't parse; features are then assumed available.
+    pub version: Option<(u32, u32, u32)>,
+    /// Whether PCRE2 is compiled in, when the output says so.
+    pub pcre2: Option<bool>,
+}
+
+impl RgInfo {
+    /// Whether this rg is at least `min`, giving it the benefit of the doubt when unknown.
+    pub fn at_least(&self, min: (u32, u32, u32)) -> bool {
+        self.version.is_none_or(|version| version >= min)
+    }
+}
+
+/// Formats a version triple the way ripgrep prints it.
+pub fn format_version((major, minor, patch): (u32, u32, u32)) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+/// Where `program` would be run from: itself if it names a file, otherwise the first match on `PATH`.
+pub fn resolve_executable(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()));
+    }
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe"] } else { &[""] };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}{}", program, ext))))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Runs `<rg_path> --version` and parses the version and PCRE2 support out of it.
+pub fn detect_rg(rg_path: &str) -> Result<RgInfo, String> {
+    let output = Command::new(rg_path)
+        .arg("--version")
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                format!("'{}' not found", rg_path)
+            } else {
+                format!("Failed to run '{}': {}", rg_path, e)
+            }
+        })?;
+    if !output.status.success() {
+        return Err(format!("'{} --version' exited with status: {}", rg_path, output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version_line = stdout.lines().next().unwrap_or_default().trim().to_string();
+    let version = version_line.split_whitespace().nth(1).and_then(|number| {
+        let mut parts = number.split(|c: char| !c.is_ascii_digit()).map(|part| part.parse::<u32>());
+        Some((parts.next()?.ok()?, parts.next()?.ok()?, parts.next().and_then(Result::ok).unwrap_or(0)))
+    });
+    // ripgrep 14+ prints a "PCRE2 ... is available" line; older builds list +pcre2 or -pcre2 features.
+    let pcre2 = stdout.lines().find_map(|line| {
+        if line.starts_with("PCRE2") {
+            Some(!line.contains("not available"))
+        } else if line.contains("+pcre2") {
+            Some(true)
+        } else if line.contains("-pcre2") {
+            Some(false)
+        } else {
+            None
+        }
+    });
+    Ok(RgInfo { version_line, version, pcre2 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_with(options: RgOptions) -> Vec<String> {
+        build_rg_args("foo", &["src".to_string()], &options)
+    }
+
+    #[test]
+    fn whole_word_emits_w() {
+        assert!(args_with(RgOptions { whole_word: true, ..Default::default() }).contains(&"-w".to_string()));
+        assert!(!args_with(RgOptions::default()).contains(&"-w".to_string()));
+    }
+
+    #[test]
+    fn query_is_passed_with_e_so_it_is_never_a_flag() {
+        for options in [RgOptions::default(), RgOptions { count_mode: true, ..Default::default() }] {
+            let args = build_rg_args("-foo", &["src".to_string()], &options);
+            let at = args.iter().position(|a| a == "-foo").expect("query missing");
+            assert_eq!(args[at - 1], "-e");
+            assert_eq!(args[at + 1], "src");
+        }
+    }
+
+    /// Whether `expected` appears in `args` as one contiguous run.
+    fn contains_run(args: &[String], expected: &[&str]) -> bool {
+        args.windows(expected.len()).any(|window| window.iter().zip(expected).all(|(a, e)| a == e))
+    }
+
+    #[test]
+    fn types_and_types_not_emit_type_flags_in_order() {
+        let args = args_with(RgOptions {
+            types: vec!["rust".to_string(), "py".to_string()],
+            types_not: vec!["toml".to_string()],
+            ..Default::default()
+        });
+        assert!(contains_run(&args, &["--type", "rust", "--type", "py", "--type-not", "toml"]), "{:?}", args);
+        assert_eq!(args.iter().filter(|a| a.starts_with("--type")).count(), 3);
+    }
+
+    #[test]
+    fn each_ignore_file_gets_its_own_flag() {
+        let args = args_with(RgOptions {
+            ignore_files: vec![" a.ignore ".to_string(), "".to_string(), "  ".to_string(), "dir/b.ignore".to_string()],
+            ..Default::default()
+        });
+        assert!(contains_run(&args, &["--ignore-file", "a.ignore", "--ignore-file", "dir/b.ignore"]), "{:?}", args);
+        assert_eq!(args.iter().filter(|a| *a == "--ignore-file").count(), 2);
+    }
+
+    #[test]
+    fn max_filesize_emitted_only_when_set() {
+        let args = args_with(RgOptions { max_filesize: Some("10M".to_string()), ..Default::default() });
+        let at = args.iter().position(|a| a == "--max-filesize").expect("--max-filesize missing");
+        assert_eq!(args[at + 1], "10M");
+        assert!(!args_with(RgOptions::default()).contains(&"--max-filesize".to_string()));
+    }
+
+    #[test]
+    fn is_valid_filesize_accepts_rg_sizes_only() {
+        assert!(is_valid_filesize("10M"));
+        assert!(is_valid_filesize("500K"));
+        assert!(is_valid_filesize("1024"));
+        assert!(!is_valid_filesize(""));
+        assert!(!is_valid_filesize("M"));
+        assert!(!is_valid_filesize("1.5M"));
+    }
+
+    #[test]
+    fn decode_base64_handles_padding() {
+        assert_eq!(decode_base64("Zm9v").as_deref(), Some(&b"foo"[..]));
+        assert_eq!(decode_base64("Zm8=").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(decode_base64("Zg==").as_deref(), Some(&b"f"[..]));
+        assert_eq!(decode_base64("").as_deref(), Some(&b""[..]));
+        assert_eq!(decode_base64("YWL/Y2QgZm9vCg==").as_deref(), Some(&b"ab\xffcd foo\n"[..]));
+    }
+
+    #[test]
+    fn decode_base64_rejects_invalid_input() {
+        assert_eq!(decode_base64("Zm9v!"), None);
+        assert_eq!(decode_base64("Zm 9v"), None);
+    }
+
+    fn parse_match(line: &str) -> Match {
+        match serde_json::from_str(line) {
+            Ok(RgJsonItem::Match(m)) => m,
+            other => panic!("not parsed as a match: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invert_match_emits_v_and_keeps_lines_without_submatches() {
+        let options = RgOptions { invert_match: true, ..Default::default() };
+        assert!(args_with(options.clone()).contains(&"-v".to_string()));
+        // `rg --json -v foo x.txt`
+        let m = parse_match(r#"{"type":"match","data":{"path":{"text":"x.txt"},"lines":{"text":"a\n"},"line_number":1,"absolute_offset":0,"submatches":[]}}"#);
+        let row = match_row(&m, &options);
+        assert_eq!((row.path.as_str(), row.line_number, row.line_text.as_str()), ("x.txt", 1, "a"));
+        assert!(row.submatches.is_empty());
+        assert_eq!(row.column, None);
+    }
+
+    #[test]
+    fn multiline_emits_u_and_keeps_newlines() {
+        let options = RgOptions { multiline: true, max_line_chars: 100, ..Default::default() };
+        assert!(args_with(options.clone()).contains(&"-U".to_string()));
+        // `rg --json -U 'foo\nbar' ml.txt`
+        let m = parse_match(r#"{"type":"match","data":{"path":{"text":"ml.txt"},"lines":{"text":"start foo\nbar end\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"foo\nbar"},"start":6,"end":13}]}}"#);
+        let row = match_row(&m, &options);
+        assert_eq!(row.line_text, "start foo\nbar end");
+        assert_eq!(row.submatches, vec![(6, 13)]);
+        assert_eq!(sanitize_line("a\nb\n"), "a\nb");
+        let (cut, submatches) = truncate_line("start foo\nbar end".to_string(), vec![(6, 13)], 10);
+        assert!(cut.contains('\n'), "{:?}", cut);
+        let (start, end) = submatches[0];
+        assert_eq!(&cut[start..end], "foo\nbar");
+    }
+
+    #[test]
+    fn replacement_is_expanded_when_rg_leaves_it_out() {
+        let options = RgOptions { replace: Some("[$1]".to_string()), ..Default::default() };
+        let replacer = Replacer::new("f(o+)", &options).unwrap();
+        // `rg --json -e 'f(o+)' -r '[$1]'` as ripgrep 14.1.1 prints it: no `replacement` field.
+        let line = r#"{"type":"match","data":{"path":{"text":"t.txt"},"lines":{"text":"bar foo2 fooo\n"},"line_number":2,"absolute_offset":8,"submatches":[{"match":{"text":"foo"},"start":4,"end":7},{"match":{"text":"fooo"},"start":9,"end":13}]}}"#;
+        let mut m = parse_match(line);
+        replacer.fill(&mut m);
+        assert_eq!(match_row(&m, &options).line_text, "bar [oo]2 [ooo]");
+        let texts: Vec<_> = only_matching_rows(&m).into_iter().map(|row| row.line_text).collect();
+        assert_eq!(texts, vec!["[oo]", "[ooo]"]);
+    }
+
+    #[test]
+    fn replacement_from_rg_is_kept() {
+        let options = RgOptions { replace: Some("[$1]".to_string()), ..Default::default() };
+        let replacer = Replacer::new("f(o+)", &options).unwrap();
+        // `rg --json -e 'f(o+)' -r '[$1]'` from ripgrep 15, which reports the replacement.
+        let line = r#"{"type":"match","data":{"path":{"text":"t.txt"},"lines":{"text":"foo one\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"foo"},"replacement":{"text":"[oo]"},"start":0,"end":3}]}}"#;
+        let mut m = parse_match(line);
+        replacer.fill(&mut m);
+        assert_eq!(match_row(&m, &options).line_text, "[oo] one");
+    }
+
+    #[test]
+    fn only_matching_emits_o_and_splits_submatches() {
+        assert!(args_with(RgOptions { only_matching: true, ..Default::default() }).contains(&"-o".to_string()));
+        // A multiline match starting on line 5: one submatch there after a two-byte char, one on line 6.
+        let m = parse_match(r#"{"type":"match","data":{"path":{"text":"a.txt"},"lines":{"text":"é foo\nfoo x\n"},"line_number":5,"absolute_offset":100,"submatches":[{"match":{"text":"foo"},"start":3,"end":6},{"match":{"text":"foo"},"start":7,"end":10}]}}"#);
+        let rows = only_matching_rows(&m);
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|row| (row.line_number, row.column, row.line_text.as_str(), row.absolute_offset))
+            .collect();
+        assert_eq!(summary, vec![(5, Some(3), "foo", Some(103)), (6, Some(1), "foo", Some(107))]);
+        assert!(rows.iter().all(|row| row.submatches == vec![(0, 3)]));
+    }
+
+    /// Saved searches persist `RgOptions`, so a renamed field would silently reset on load.
+    #[test]
+    fn options_keep_every_field_through_serde() {
+        let fixture = serde_json::json!({
+            "rg_path": "/opt/rg",
+            "case_mode": "Insensitive",
+            "search_hidden": true,
+            "follow_symlinks": true,
+            "ignore_level": "Unrestricted",
+            "ignore_files": ["extra.ignore"],
+            "binary_mode": "Binary",
+            "encoding": "utf-16le",
+            "max_depth": 3,
+            "max_filesize": "10M",
+            "threads": 2,
+            "include_globs": ["*.rs"],
+            "exclude_globs": ["*.log"],
+            "glob_case_insensitive": true,
+            "types": ["rust"],
+            "types_not": ["toml"],
+            "fixed_string": true,
+            "whole_word": true,
+            "multiline": true,
+            "multiline_dotall": true,
+            "pcre2": true,
+            "invert_match": true,
+            "only_matching": true,
+            "replace": "bar",
+            "sort": "ModifiedNewest",
+            "count_mode": true,
+            "context_before": 1,
+            "context_after": 2,
+            "max_count_per_file": 5,
+            "max_results": 7,
+            "extra_args": ["--no-messages"],
+            "max_line_chars": 80,
+            "file_timings": true,
+            "filename_mode": true,
+            "line_range": [10, 20],
+        });
+        let options: RgOptions = serde_json::from_value(fixture.clone()).unwrap();
+        let round_trip = serde_json::to_value(&options).unwrap();
+        for (key, value) in fixture.as_object().unwrap() {
+            assert_eq!(round_trip.get(key), Some(value), "field {:?}", key);
+        }
+    }
+
+    // Lines below are verbatim `rg --json -A1` output.
+
+    #[test]
+    fn parses_match_line() {
+        let line = r#"{"type":"match","data":{"path":{"text":"x.txt"},"lines":{"text":"foo one\n"},"line_number":2,"absolute_offset":2,"submatches":[{"match":{"text":"foo"},"start":0,"end":3}]}}"#;
+        let Ok(RgJsonItem::Match(m)) = serde_json::from_str(line) else {
+            panic!("not parsed as a match");
+        };
+        assert_eq!(m.path.text_or_bytes.to_string_lossy(), "x.txt");
+        assert_eq!(display_text(&m.lines), "foo one");
+        assert_eq!(m.line_number, Some(2));
+        assert_eq!(m.absolute_offset, 2);
+        assert_eq!((m.submatches[0].start, m.submatches[0].end), (0, 3));
+    }
+
+    #[test]
+    fn parses_match_line_with_invalid_utf8() {
+        let line = r#"{"type":"match","data":{"path":{"text":"nonutf.txt"},"lines":{"bytes":"YWL/Y2QgZm9vCg=="},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"foo"},"start":6,"end":9}]}}"#;
+        let Ok(RgJsonItem::Match(m)) = serde_json::from_str(line) else {
+            panic!("not parsed as a match");
+        };
+        assert_eq!(display_text(&m.lines), "ab\u{fffd}cd foo");
+        // The invalid byte grew to a 3-byte U+FFFD, so the highlight moves with it.
+        let row = match_row(&m, &RgOptions::default());
+        let (start, end) = row.submatches[0];
+        assert_eq!(&row.line_text[start..end], "foo");
+        assert_eq!(row.column, Some(7));
+        assert_eq!(row.absolute_offset, Some(6));
+    }
+
+    #[test]
+    fn parses_context_line() {
+        let line = r#"{"type":"context","data":{"path":{"text":"x.txt"},"lines":{"text":"b\n"},"line_number":3,"absolute_offset":10,"submatches":[]}}"#;
+        let Ok(RgJsonItem::Context(c)) = serde_json::from_str(line) else {
+            panic!("not parsed as context");
+        };
+        assert_eq!(display_text(&c.lines), "b");
+        assert_eq!(c.line_number, Some(3));
+    }
+
+    #[test]
+    fn parses_end_line() {
+        let line = r#"{"type":"end","data":{"path":{"text":"x.txt"},"binary_offset":null,"stats":{"elapsed":{"secs":0,"nanos":28800,"human":"0.000029s"},"searches":1,"searches_with_match":1,"bytes_searched":38,"bytes_printed":957,"matched_lines":3,"matches":3}}}"#;
+        let Ok(RgJsonItem::End(end)) = serde_json::from_str(line) else {
+            panic!("not parsed as an end");
+        };
+        assert_eq!(end.binary_offset, None);
+        assert_eq!(end.stats.matches, 3);
+        assert_eq!(end.stats.elapsed.to_duration(), Duration::from_nanos(28800));
+    }
+
+    #[test]
+    fn parses_summary_line() {
+        let line = r#"{"data":{"elapsed_total":{"human":"0.000867s","nanos":866801,"secs":0},"stats":{"bytes_printed":957,"bytes_searched":38,"elapsed":{"human":"0.000029s","nanos":28800,"secs":0},"matched_lines":3,"matches":3,"searches":1,"searches_with_match":1}},"type":"summary"}"#;
+        let Ok(RgJsonItem::Summary(summary)) = serde_json::from_str(line) else {
+            panic!("not parsed as a summary");
+        };
+        assert_eq!(summary.stats.searches_with_match, 1);
+        assert_eq!(summary.elapsed_total.to_duration(), Duration::from_nanos(866801));
+    }
+}